@@ -0,0 +1,536 @@
+//! Generates a statically defined dictionary type from an EDS.
+//!
+//! The generated type has no heap-allocated dictionary or map, but it is not
+//! allocation free. String and domain entries are stored as `String` and
+//! `Vec<u8>`, subscribers are kept in a `Vec` of `Rc`, and `ObjectAccess`
+//! returns owned `ObjectValue`s. Targets without an allocator need their own
+//! storage for these.
+
+use std::collections::HashSet;
+
+use crate::eds::{self, EdsError};
+use crate::od::{ObjectDictionary, ObjectType, ObjectValue};
+
+const RESERVED: [&str; 58] = [
+    "abstract",
+    "as",
+    "async",
+    "await",
+    "become",
+    "box",
+    "break",
+    "const",
+    "continue",
+    "crate",
+    "default",
+    "do",
+    "dyn",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "final",
+    "fn",
+    "for",
+    "if",
+    "impl",
+    "in",
+    "let",
+    "loop",
+    "macro",
+    "match",
+    "missing_entry_error",
+    "mod",
+    "move",
+    "mut",
+    "new",
+    "notify_subscribers",
+    "override",
+    "priv",
+    "pub",
+    "read",
+    "ref",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "subscribe",
+    "subscribers",
+    "super",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "unsafe",
+    "unsized",
+    "use",
+    "virtual",
+    "where",
+    "while",
+    "write",
+    "yield",
+];
+
+struct Field {
+    index: u16,
+    sub_index: u8,
+    name: String,
+    value: ObjectValue,
+    writable: bool,
+    low_limit: Option<ObjectValue>,
+    high_limit: Option<ObjectValue>,
+}
+
+/// Generates the source of a statically defined dictionary type named
+/// `type_name` from an EDS. Meant to be called from a build script, with the
+/// output written to `OUT_DIR` and pulled in with `include!`. Every entry
+/// becomes a typed field with a getter, writable entries also get a `set_`
+/// prefixed setter, and the type implements `od::ObjectAccess`.
+pub fn generate(eds: &str, node_id: u8, type_name: &str) -> Result<String, EdsError> {
+    let od = eds::parse(eds, node_id)?;
+    let fields = collect_fields(&od);
+
+    let mut out = String::new();
+    out.push_str("// Generated by canopen_rs::codegen, do not edit.\n\n");
+    out.push_str("use std::cell::RefCell;\nuse std::rc::Rc;\n\n");
+    out.push_str(
+        "use canopen_rs::od::{ObjectAccess, ObjectDictionaryError, ObjectSubscriber, ObjectValue};\n\n",
+    );
+
+    out.push_str(&format!("pub struct {} {{\n", type_name));
+    for field in fields.iter() {
+        out.push_str(&format!(
+            "    {}: {},\n",
+            field.name,
+            rust_type(&field.value)
+        ));
+    }
+    out.push_str(&format!(
+        "    subscribers: Vec<{}Subscription>,\n}}\n\n",
+        type_name
+    ));
+    out.push_str(&format!(
+        "type {}Subscription = (u16, u8, Rc<RefCell<dyn ObjectSubscriber>>);\n\n",
+        type_name
+    ));
+
+    // Firmware rarely touches every entry, unused accessors are expected.
+    out.push_str(&format!("#[allow(dead_code)]\nimpl {} {{\n", type_name));
+    out.push_str(&format!("    pub fn new() -> {} {{\n", type_name));
+    out.push_str(&format!("        {} {{\n", type_name));
+    for field in fields.iter() {
+        out.push_str(&format!(
+            "            {}: {},\n",
+            field.name,
+            literal(&field.value)
+        ));
+    }
+    out.push_str("            subscribers: Vec::new(),\n        }\n    }\n");
+    for field in fields.iter() {
+        write_accessors(&mut out, field);
+    }
+    write_helpers(&mut out, &od);
+    out.push_str("}\n\n");
+
+    out.push_str(&format!(
+        "impl Default for {} {{\n    fn default() -> Self {{\n        Self::new()\n    }}\n}}\n\n",
+        type_name
+    ));
+
+    out.push_str(&format!("impl ObjectAccess for {} {{\n", type_name));
+    write_read(&mut out, &fields);
+    write_write(&mut out, &fields);
+    out.push_str(
+        "
+    fn subscribe(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.read(index, sub_index)?;
+        self.subscribers.push((index, sub_index, subscriber));
+        Ok(())
+    }
+}
+",
+    );
+
+    Ok(out)
+}
+
+fn collect_fields(od: &ObjectDictionary) -> Vec<Field> {
+    let entries = od.entries();
+    let mut used = HashSet::new();
+    let mut fields = Vec::new();
+    for (index, sub_index) in entries.iter().cloned() {
+        let attributes = od.attributes(index, sub_index).expect("entry exists");
+        let entry_name = attributes.name.as_deref().unwrap_or("");
+        let object_type = od.object_type(index).expect("entry exists");
+
+        let name = if let ObjectType::Var | ObjectType::Domain = object_type {
+            identifier(entry_name)
+        } else {
+            let object_name = od.object_name(index).ok().flatten().unwrap_or("");
+            match (identifier(object_name), identifier(entry_name)) {
+                (object, entry) if object.is_empty() || entry.is_empty() => String::new(),
+                (object, entry) => format!("{}_{}", object, entry),
+            }
+        };
+        let name = if name.is_empty() {
+            format!("object_{:04x}_sub{}", index, sub_index)
+        } else if used.contains(&name) || RESERVED.contains(&name.as_str()) {
+            format!("{}_{:04x}_sub{}", name, index, sub_index)
+        } else {
+            name
+        };
+        used.insert(name.clone());
+
+        fields.push(Field {
+            index,
+            sub_index,
+            name,
+            value: od.read(index, sub_index).expect("entry exists"),
+            writable: attributes.access_type.is_writable(),
+            low_limit: attributes.low_limit.clone(),
+            high_limit: attributes.high_limit.clone(),
+        });
+    }
+    fields
+}
+
+/// Snake case identifier from a parameter name, empty if nothing usable
+/// remains.
+fn identifier(name: &str) -> String {
+    let mut ident = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            ident.push(c.to_ascii_lowercase());
+        } else if !ident.is_empty() && !ident.ends_with('_') {
+            ident.push('_');
+        }
+    }
+    let ident = ident.trim_end_matches('_');
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("object_{}", ident)
+    } else {
+        ident.to_string()
+    }
+}
+
+fn write_accessors(out: &mut String, field: &Field) {
+    let (return_type, getter) = match field.value {
+        ObjectValue::VisibleString(_)
+        | ObjectValue::OctetString(_)
+        | ObjectValue::UnicodeString(_) => ("&str", format!("&self.{}", field.name)),
+        ObjectValue::Domain(_) => ("&[u8]", format!("&self.{}", field.name)),
+        _ => (rust_type(&field.value), format!("self.{}", field.name)),
+    };
+    out.push_str(&format!(
+        "
+    pub fn {name}(&self) -> {return_type} {{
+        {getter}
+    }}
+",
+        name = field.name,
+        return_type = return_type,
+        getter = getter,
+    ));
+    if field.writable {
+        out.push_str(&format!(
+            "
+    pub fn set_{name}(&mut self, value: {value_type}) -> Result<(), ObjectDictionaryError> {{
+        self.write({index:#06X}, {sub_index:#04X}, ObjectValue::{variant}(value))
+    }}
+",
+            name = field.name,
+            value_type = rust_type(&field.value),
+            index = field.index,
+            sub_index = field.sub_index,
+            variant = variant(&field.value),
+        ));
+    }
+}
+
+fn write_helpers(out: &mut String, od: &ObjectDictionary) {
+    let mut indices: Vec<String> = od
+        .entries()
+        .iter()
+        .map(|(index, _)| format!("{:#06X}", index))
+        .collect();
+    indices.dedup();
+    let (parameter, body) = if indices.is_empty() {
+        (
+            "_index: u16",
+            String::from("ObjectDictionaryError::ObjectDoesNotExist"),
+        )
+    } else {
+        (
+            "index: u16",
+            format!(
+                "match index {{
+            {} => ObjectDictionaryError::SubIndexDoesNotExist,
+            _ => ObjectDictionaryError::ObjectDoesNotExist,
+        }}",
+                indices.join(" | ")
+            ),
+        )
+    };
+    out.push_str(&format!(
+        "
+    fn notify_subscribers(&self, index: u16, sub_index: u8) -> Result<(), ObjectDictionaryError> {{
+        let value = self.read(index, sub_index)?;
+        for (i, s, subscriber) in self.subscribers.iter() {{
+            if *i == index && *s == sub_index {{
+                subscriber.borrow_mut().object_updated(index, sub_index, &value);
+            }}
+        }}
+        Ok(())
+    }}
+
+    fn missing_entry_error({}) -> ObjectDictionaryError {{
+        {}
+    }}
+",
+        parameter, body
+    ));
+}
+
+fn write_read(out: &mut String, fields: &[Field]) {
+    if fields.is_empty() {
+        out.push_str(
+            "    fn read(&self, index: u16, _sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        Err(Self::missing_entry_error(index))
+    }
+",
+        );
+        return;
+    }
+    out.push_str(
+        "    fn read(&self, index: u16, sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        match (index, sub_index) {
+",
+    );
+    for field in fields.iter() {
+        let value = match field.value {
+            ObjectValue::VisibleString(_)
+            | ObjectValue::OctetString(_)
+            | ObjectValue::UnicodeString(_)
+            | ObjectValue::Domain(_) => format!("self.{}.clone()", field.name),
+            _ => format!("self.{}", field.name),
+        };
+        out.push_str(&format!(
+            "            ({:#06X}, {:#04X}) => Ok(ObjectValue::{}({})),\n",
+            field.index,
+            field.sub_index,
+            variant(&field.value),
+            value
+        ));
+    }
+    out.push_str(
+        "            _ => Err(Self::missing_entry_error(index)),
+        }
+    }
+",
+    );
+}
+
+fn write_write(out: &mut String, fields: &[Field]) {
+    if fields.is_empty() {
+        out.push_str(
+            "
+    fn write(
+        &mut self,
+        index: u16,
+        _sub_index: u8,
+        _value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        Err(Self::missing_entry_error(index))
+    }
+",
+        );
+        return;
+    }
+    out.push_str(
+        "
+    fn write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        match (index, sub_index, value) {
+",
+    );
+    for field in fields.iter() {
+        let pattern = format!(
+            "            ({:#06X}, {:#04X}, ObjectValue::{}(value)) => ",
+            field.index,
+            field.sub_index,
+            variant(&field.value)
+        );
+        let mut checks = String::new();
+        let ordered = is_ordered(&field.value);
+        if let Some(limit) = field
+            .low_limit
+            .as_ref()
+            .filter(|l| ordered && !is_extreme(l, false))
+        {
+            checks.push_str(&format!(
+                "                if value < {} {{\n                    return Err(ObjectDictionaryError::ValueTooLow);\n                }}\n",
+                literal(limit)
+            ));
+        }
+        if let Some(limit) = field
+            .high_limit
+            .as_ref()
+            .filter(|l| ordered && !is_extreme(l, true))
+        {
+            checks.push_str(&format!(
+                "                if value > {} {{\n                    return Err(ObjectDictionaryError::ValueTooHigh);\n                }}\n",
+                literal(limit)
+            ));
+        }
+        if checks.is_empty() {
+            out.push_str(&format!("{}self.{} = value,\n", pattern, field.name));
+        } else {
+            out.push_str(&format!(
+                "{}{{\n{}                self.{} = value;\n            }}\n",
+                pattern, checks, field.name
+            ));
+        }
+    }
+    out.push_str(
+        "            (index, sub_index, _) => {
+                return Err(match self.read(index, sub_index) {
+                    Ok(_) => ObjectDictionaryError::DataTypeMismatch,
+                    Err(error) => error,
+                })
+            }
+        }
+        self.notify_subscribers(index, sub_index)
+    }
+",
+    );
+}
+
+/// Whether limits apply to a value, like the dictionary only integers and
+/// reals are compared.
+fn is_ordered(value: &ObjectValue) -> bool {
+    !matches!(
+        value,
+        ObjectValue::Boolean(_)
+            | ObjectValue::VisibleString(_)
+            | ObjectValue::OctetString(_)
+            | ObjectValue::UnicodeString(_)
+            | ObjectValue::TimeOfDay(_)
+            | ObjectValue::TimeDifference(_)
+            | ObjectValue::Domain(_)
+    )
+}
+
+/// Whether a limit is the smallest or largest value of its integer type, in
+/// which case a comparison against it would always be false.
+fn is_extreme(limit: &ObjectValue, high: bool) -> bool {
+    let bytes = limit.to_bytes();
+    let signed = matches!(
+        limit,
+        ObjectValue::Integer8(_)
+            | ObjectValue::Integer16(_)
+            | ObjectValue::Integer24(_)
+            | ObjectValue::Integer32(_)
+            | ObjectValue::Integer40(_)
+            | ObjectValue::Integer48(_)
+            | ObjectValue::Integer56(_)
+            | ObjectValue::Integer64(_)
+    );
+    let unsigned = matches!(
+        limit,
+        ObjectValue::Unsigned8(_)
+            | ObjectValue::Unsigned16(_)
+            | ObjectValue::Unsigned24(_)
+            | ObjectValue::Unsigned32(_)
+            | ObjectValue::Unsigned40(_)
+            | ObjectValue::Unsigned48(_)
+            | ObjectValue::Unsigned56(_)
+            | ObjectValue::Unsigned64(_)
+    );
+    let (last, rest) = match bytes.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    match (signed, unsigned, high) {
+        (true, _, true) => *last == 0x7F && rest.iter().all(|b| *b == 0xFF),
+        (true, _, false) => *last == 0x80 && rest.iter().all(|b| *b == 0),
+        (_, true, true) => bytes.iter().all(|b| *b == 0xFF),
+        (_, true, false) => bytes.iter().all(|b| *b == 0),
+        _ => false,
+    }
+}
+
+fn rust_type(value: &ObjectValue) -> &'static str {
+    match value {
+        ObjectValue::Boolean(_) => "bool",
+        ObjectValue::Integer8(_) => "i8",
+        ObjectValue::Integer16(_) => "i16",
+        ObjectValue::Integer24(_) | ObjectValue::Integer32(_) => "i32",
+        ObjectValue::Integer40(_)
+        | ObjectValue::Integer48(_)
+        | ObjectValue::Integer56(_)
+        | ObjectValue::Integer64(_) => "i64",
+        ObjectValue::Unsigned8(_) => "u8",
+        ObjectValue::Unsigned16(_) => "u16",
+        ObjectValue::Unsigned24(_) | ObjectValue::Unsigned32(_) => "u32",
+        ObjectValue::Unsigned40(_)
+        | ObjectValue::Unsigned48(_)
+        | ObjectValue::Unsigned56(_)
+        | ObjectValue::Unsigned64(_) => "u64",
+        ObjectValue::Real32(_) => "f32",
+        ObjectValue::Real64(_) => "f64",
+        ObjectValue::VisibleString(_)
+        | ObjectValue::OctetString(_)
+        | ObjectValue::UnicodeString(_) => "String",
+        ObjectValue::TimeOfDay(_) => "canopen_rs::od::TimeOfDay",
+        ObjectValue::TimeDifference(_) => "canopen_rs::od::TimeDifference",
+        ObjectValue::Domain(_) => "Vec<u8>",
+    }
+}
+
+fn literal(value: &ObjectValue) -> String {
+    match value {
+        ObjectValue::Boolean(v) => format!("{}", v),
+        ObjectValue::Integer8(v) => format!("{}", v),
+        ObjectValue::Integer16(v) => format!("{}", v),
+        ObjectValue::Integer24(v) | ObjectValue::Integer32(v) => format!("{}", v),
+        ObjectValue::Integer40(v)
+        | ObjectValue::Integer48(v)
+        | ObjectValue::Integer56(v)
+        | ObjectValue::Integer64(v) => format!("{}", v),
+        ObjectValue::Unsigned8(v) => format!("{:#X}", v),
+        ObjectValue::Unsigned16(v) => format!("{:#X}", v),
+        ObjectValue::Unsigned24(v) | ObjectValue::Unsigned32(v) => format!("{:#X}", v),
+        ObjectValue::Unsigned40(v)
+        | ObjectValue::Unsigned48(v)
+        | ObjectValue::Unsigned56(v)
+        | ObjectValue::Unsigned64(v) => format!("{:#X}", v),
+        ObjectValue::Real32(v) => format!("f32::from_bits({:#010X})", v.to_bits()),
+        ObjectValue::Real64(v) => format!("f64::from_bits({:#018X})", v.to_bits()),
+        ObjectValue::VisibleString(v)
+        | ObjectValue::OctetString(v)
+        | ObjectValue::UnicodeString(v) => format!("String::from({:?})", v),
+        ObjectValue::TimeOfDay(v) => format!(
+            "canopen_rs::od::TimeOfDay {{ milliseconds: {}, days: {} }}",
+            v.milliseconds, v.days
+        ),
+        ObjectValue::TimeDifference(v) => format!(
+            "canopen_rs::od::TimeDifference {{ milliseconds: {}, days: {} }}",
+            v.milliseconds, v.days
+        ),
+        ObjectValue::Domain(v) => format!("vec!{:?}", v),
+    }
+}
+
+fn variant(value: &ObjectValue) -> String {
+    format!("{:?}", value.data_type())
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+
+use crate::od::{ObjectDictionary, ObjectDictionaryError, ObjectValue};
+
+/// One record of a CiA 302 concise DCF.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConciseDcfEntry {
+    pub index: u16,
+    pub sub_index: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConciseDcfError {
+    /// The blob ends in the middle of the entry with this position.
+    Truncated { entry: usize },
+    /// There are bytes left after the last entry.
+    TrailingData,
+    /// The dictionary rejected an entry.
+    EntryFailed {
+        index: u16,
+        sub_index: u8,
+        error: ObjectDictionaryError,
+    },
+}
+
+impl fmt::Display for ConciseDcfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConciseDcfError::Truncated { entry } => {
+                write!(f, "concise DCF truncated in entry {}", entry)
+            }
+            ConciseDcfError::TrailingData => write!(f, "concise DCF has trailing data"),
+            ConciseDcfError::EntryFailed {
+                index,
+                sub_index,
+                error,
+            } => write!(f, "entry {:#06X}sub{} failed: {}", index, sub_index, error),
+        }
+    }
+}
+
+impl std::error::Error for ConciseDcfError {}
+
+/// Decodes a concise DCF, a 32-bit entry count followed by (index, sub-index,
+/// size, data) records, all little-endian.
+pub fn parse(data: &[u8]) -> Result<Vec<ConciseDcfEntry>, ConciseDcfError> {
+    let mut reader = Reader { data, position: 0 };
+    let count = reader
+        .u32()
+        .ok_or(ConciseDcfError::Truncated { entry: 0 })?;
+
+    let mut entries = Vec::new();
+    for entry in 1..=count as usize {
+        let truncated = ConciseDcfError::Truncated { entry };
+        let index = reader.u16().ok_or_else(|| truncated.clone())?;
+        let sub_index = reader.u8().ok_or_else(|| truncated.clone())?;
+        let size = reader.u32().ok_or_else(|| truncated.clone())?;
+        let data = reader.bytes(size as usize).ok_or(truncated)?;
+        entries.push(ConciseDcfEntry {
+            index,
+            sub_index,
+            data: data.to_vec(),
+        });
+    }
+
+    if reader.position != data.len() {
+        return Err(ConciseDcfError::TrailingData);
+    }
+    Ok(entries)
+}
+
+pub fn generate(entries: &[ConciseDcfEntry]) -> Vec<u8> {
+    let mut data = (entries.len() as u32).to_le_bytes().to_vec();
+    for entry in entries.iter() {
+        data.extend_from_slice(&entry.index.to_le_bytes());
+        data.push(entry.sub_index);
+        data.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+        data.extend_from_slice(&entry.data);
+    }
+    data
+}
+
+/// Collects the current values of the given entries.
+pub fn entries_from(
+    od: &ObjectDictionary,
+    keys: &[(u16, u8)],
+) -> Result<Vec<ConciseDcfEntry>, ObjectDictionaryError> {
+    keys.iter()
+        .map(|(index, sub_index)| {
+            Ok(ConciseDcfEntry {
+                index: *index,
+                sub_index: *sub_index,
+                data: od.read(*index, *sub_index)?.to_bytes(),
+            })
+        })
+        .collect()
+}
+
+/// Writes every entry of a concise DCF into the dictionary in order, as a
+/// remote node would through SDO. Access types, limits and validators apply.
+/// Entries before a failing one stay applied.
+pub fn apply(od: &mut ObjectDictionary, data: &[u8]) -> Result<(), ConciseDcfError> {
+    apply_entries(od, data, true)
+}
+
+/// Like `apply`, but writes locally so read-only and const entries can be
+/// set too. Only meant for data the device stored itself, such as saved
+/// parameters.
+pub fn apply_trusted(od: &mut ObjectDictionary, data: &[u8]) -> Result<(), ConciseDcfError> {
+    apply_entries(od, data, false)
+}
+
+fn apply_entries(
+    od: &mut ObjectDictionary,
+    data: &[u8],
+    remote: bool,
+) -> Result<(), ConciseDcfError> {
+    for entry in parse(data)? {
+        apply_entry(od, &entry, remote).map_err(|error| ConciseDcfError::EntryFailed {
+            index: entry.index,
+            sub_index: entry.sub_index,
+            error,
+        })?;
+    }
+    Ok(())
+}
+
+fn apply_entry(
+    od: &mut ObjectDictionary,
+    entry: &ConciseDcfEntry,
+    remote: bool,
+) -> Result<(), ObjectDictionaryError> {
+    let data_type = od.data_type(entry.index, entry.sub_index)?;
+    let value = ObjectValue::from_bytes(data_type, &entry.data)?;
+    if remote {
+        od.remote_write(entry.index, entry.sub_index, value)
+    } else {
+        od.write(entry.index, entry.sub_index, value)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, size: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(size)?;
+        let bytes = self.data.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
@@ -1,6 +1,21 @@
-use crate::cob::Cob;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::cob::{get_p2p_cob_id, Cob};
 use crate::message::CanMessage;
+use crate::od::{
+    AccessType, ObjectAttributes, ObjectDictionary, ObjectType, ObjectValue, ParameterArea,
+};
 use crate::service::node_control::*;
+use crate::service::pdo::{PdoValidator, PDO_COUNT, RPDO_COMMUNICATION, TPDO_COMMUNICATION};
+use crate::service::rpdo::ReceivePdos;
+use crate::service::sdo_client::SdoClient;
+use crate::service::sdo_server::*;
+use crate::service::tpdo::TransmitPdos;
+use crate::storage::{self, Storage, StoreCommands};
+
+pub use crate::service::sdo_client::{SdoClientError, SdoClientResult, SdoOutcome};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum NmtState {
@@ -10,41 +25,111 @@ pub enum NmtState {
     Stopped,
 }
 
+/// Device type (0x1000) and identity object (0x1018) contents.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Identity {
+    pub device_type: u32,
+    pub vendor_id: u32,
+    pub product_code: u32,
+    pub revision_number: u32,
+    pub serial_number: u32,
+}
+
 pub struct CanOpenController {
     node_id: u8,
+    identity: Option<Identity>,
     nmt_state: NmtState,
+    od: ObjectDictionary,
+    sdo_server: SdoServer,
+    sdo_client: SdoClient,
+    rpdos: ReceivePdos,
+    tpdos: TransmitPdos,
+    store_commands: Rc<RefCell<StoreCommands>>,
     outgoing_messages: Vec<CanMessage>,
 }
 
 impl CanOpenController {
     pub fn new(node_id: u8) -> CanOpenController {
+        CanOpenController::create(node_id, None)
+    }
+
+    pub fn with_identity(node_id: u8, identity: Identity) -> CanOpenController {
+        CanOpenController::create(node_id, Some(identity))
+    }
+
+    fn create(node_id: u8, identity: Option<Identity>) -> CanOpenController {
         CanOpenController {
             node_id,
+            identity,
             nmt_state: NmtState::Initialising,
+            od: ObjectDictionary::new(),
+            sdo_server: SdoServer::new(node_id),
+            sdo_client: SdoClient::new(),
+            rpdos: ReceivePdos::new(),
+            tpdos: TransmitPdos::new(),
+            store_commands: Rc::new(RefCell::new(StoreCommands::default())),
             outgoing_messages: Vec::new(),
         }
     }
 
     pub fn init(&mut self) {
-        // TODO: Setup object dictionary
+        self.setup_object_dictionary();
+        self.rpdos.subscribe(&mut self.od);
+        self.tpdos.subscribe(&mut self.od);
 
         self.reset_node();
     }
 
     pub fn process(&mut self, can_message: CanMessage) {
-        if let Cob::Nmt = can_message.cob() {
-            match handle_nmt_message(self.node_id, can_message) {
+        if can_message.is_rtr() {
+            if self.nmt_state == NmtState::Operational {
+                let messages = self.tpdos.remote_request(&self.od, &can_message);
+                self.outgoing_messages.extend(messages);
+            }
+            return;
+        }
+
+        match can_message.cob() {
+            Cob::Nmt => match handle_nmt_message(self.node_id, can_message) {
                 NodeCommand::StartNode => self.set_nmt_state(NmtState::Operational),
                 NodeCommand::StopNode => self.set_nmt_state(NmtState::Stopped),
                 NodeCommand::EnterPreOperational => self.set_nmt_state(NmtState::PreOperational),
                 NodeCommand::ResetNode => self.reset_node(),
                 NodeCommand::ResetCommunication => self.reset_communication(),
                 _ => {}
+            },
+            Cob::SdoRx if self.nmt_state != NmtState::Stopped => {
+                let responses = self.sdo_server.process(&mut self.od, can_message);
+                self.outgoing_messages.extend(responses);
+            }
+            Cob::SdoTx => {
+                if let Some(request) = self.sdo_client.process(can_message) {
+                    self.outgoing_messages.push(request);
+                }
+            }
+            Cob::Sync if self.nmt_state == NmtState::Operational => {
+                self.rpdos.sync(&mut self.od);
+                let messages = self.tpdos.sync(&self.od);
+                self.outgoing_messages.extend(messages);
             }
+            _ if self.nmt_state == NmtState::Operational => {
+                self.rpdos.process(&mut self.od, &can_message);
+            }
+            _ => {}
         }
     }
 
-    pub fn update(&mut self, _dt: std::time::Duration) {}
+    pub fn update(&mut self, dt: Duration) {
+        if let Some(abort) = self.sdo_server.update(dt) {
+            self.outgoing_messages.push(abort);
+        }
+        let requests = self.sdo_client.update(dt);
+        self.outgoing_messages.extend(requests);
+        if self.nmt_state == NmtState::Operational {
+            let messages = self.tpdos.update(&self.od, dt);
+            self.outgoing_messages.extend(messages);
+        }
+    }
 
     pub fn fetch(&mut self) -> Vec<CanMessage> {
         let mut messages = Vec::new();
@@ -54,26 +139,299 @@ impl CanOpenController {
         messages
     }
 
+    pub fn upload(&mut self, node_id: u8, index: u16, sub_index: u8) -> Result<(), SdoClientError> {
+        let request = self.sdo_client.upload(node_id, index, sub_index)?;
+        self.outgoing_messages.push(request);
+        Ok(())
+    }
+
+    pub fn download(
+        &mut self,
+        node_id: u8,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), SdoClientError> {
+        let request = self.sdo_client.download(node_id, index, sub_index, value)?;
+        self.outgoing_messages.push(request);
+        Ok(())
+    }
+
+    pub fn fetch_sdo_results(&mut self) -> Vec<SdoClientResult> {
+        self.sdo_client.fetch_results()
+    }
+
+    pub fn set_sdo_timeout(&mut self, timeout: Duration) {
+        self.sdo_server.set_timeout(timeout);
+        self.sdo_client.set_timeout(timeout);
+    }
+
+    /// Backend for the parameters saved through 0x1010, must be set before
+    /// `init` for stored parameters to be loaded.
+    pub fn set_storage(&mut self, storage: Box<dyn Storage>) {
+        self.store_commands.borrow_mut().storage = Some(storage);
+    }
+
     pub fn nmt_state(&self) -> NmtState {
         self.nmt_state
     }
 
+    pub fn object_dictionary(&self) -> &ObjectDictionary {
+        &self.od
+    }
+
+    pub fn object_dictionary_mut(&mut self) -> &mut ObjectDictionary {
+        &mut self.od
+    }
+
+    /// Adds the mandatory communication objects that the application has not
+    /// already provided. A supplied identity always replaces 0x1000 and 0x1018.
+    fn setup_object_dictionary(&mut self) {
+        let identity = self.identity.unwrap_or_default();
+        let overwrite = self.identity.is_some();
+        let read_only = |name: &str| ObjectAttributes {
+            access_type: AccessType::ReadOnly,
+            name: Some(String::from(name)),
+            ..Default::default()
+        };
+
+        let entries = [
+            (
+                0x1000,
+                0x00,
+                ObjectValue::Unsigned32(identity.device_type),
+                read_only("Device type"),
+                overwrite,
+            ),
+            (
+                0x1001,
+                0x00,
+                ObjectValue::Unsigned8(0),
+                ObjectAttributes {
+                    pdo_mappable: true,
+                    ..read_only("Error register")
+                },
+                false,
+            ),
+            (
+                0x1017,
+                0x00,
+                ObjectValue::Unsigned16(0),
+                ObjectAttributes {
+                    name: Some(String::from("Producer heartbeat time")),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                0x1018,
+                0x00,
+                ObjectValue::Unsigned8(4),
+                ObjectAttributes {
+                    access_type: AccessType::Const,
+                    ..read_only("Highest sub-index supported")
+                },
+                overwrite,
+            ),
+            (
+                0x1018,
+                0x01,
+                ObjectValue::Unsigned32(identity.vendor_id),
+                read_only("Vendor-ID"),
+                overwrite,
+            ),
+            (
+                0x1018,
+                0x02,
+                ObjectValue::Unsigned32(identity.product_code),
+                read_only("Product code"),
+                overwrite,
+            ),
+            (
+                0x1018,
+                0x03,
+                ObjectValue::Unsigned32(identity.revision_number),
+                read_only("Revision number"),
+                overwrite,
+            ),
+            (
+                0x1018,
+                0x04,
+                ObjectValue::Unsigned32(identity.serial_number),
+                read_only("Serial number"),
+                overwrite,
+            ),
+        ];
+
+        for (index, sub_index, value, attributes, overwrite) in entries.iter().cloned() {
+            if overwrite || self.od.read(index, sub_index).is_err() {
+                self.od
+                    .add_with_attributes(index, sub_index, value, attributes);
+            }
+        }
+
+        self.setup_store_objects();
+        self.setup_pdo_objects();
+    }
+
+    /// Store parameters (0x1010) and restore default parameters (0x1011),
+    /// sub-index 1 to 3 address all, communication and application parameters.
+    /// An application that provides either object handles its commands itself.
+    fn setup_store_objects(&mut self) {
+        let names = [
+            "all parameters",
+            "communication parameters",
+            "application parameters",
+        ];
+        // Bit 0 of the value tells that parameters are saved on command.
+        let flags = self.store_commands.borrow().storage.is_some() as u32;
+        let entries = self.od.entries();
+        for (index, action) in [(0x1010, "Save"), (0x1011, "Restore default")].iter() {
+            if entries.iter().any(|(i, _)| i == index) {
+                continue;
+            }
+            self.od.add_with_attributes(
+                *index,
+                0x00,
+                ObjectValue::Unsigned8(names.len() as u8),
+                ObjectAttributes {
+                    access_type: AccessType::Const,
+                    name: Some(String::from("Highest sub-index supported")),
+                    ..Default::default()
+                },
+            );
+            for (sub_index, name) in (1..).zip(names.iter()) {
+                self.od.add_with_attributes(
+                    *index,
+                    sub_index,
+                    ObjectValue::Unsigned32(flags),
+                    ObjectAttributes {
+                        name: Some(format!("{} {}", action, name)),
+                        ..Default::default()
+                    },
+                );
+                self.od
+                    .set_command(*index, sub_index, self.store_commands.clone())
+                    .expect("entry was just added");
+            }
+        }
+    }
+
+    /// Communication and mapping parameters of the four default RPDOs
+    /// (0x1400, 0x1600) and TPDOs (0x1800, 0x1A00), unless the application
+    /// provides them. The mappings start out empty. Remote changes to any
+    /// PDO parameters are checked by `PdoValidator`.
+    fn setup_pdo_objects(&mut self) {
+        let rpdos = [Cob::Pdo1Rx, Cob::Pdo2Rx, Cob::Pdo3Rx, Cob::Pdo4Rx];
+        for (index, cob) in (RPDO_COMMUNICATION..).zip(rpdos.iter()) {
+            let cob_id = get_p2p_cob_id(self.node_id, *cob) as u32;
+            self.add_pdo_objects(
+                index,
+                "RPDO",
+                &[
+                    (0x01, "COB-ID used by RPDO", ObjectValue::Unsigned32(cob_id)),
+                    (0x02, "Transmission type", ObjectValue::Unsigned8(0xFF)),
+                ],
+            );
+        }
+
+        let tpdos = [Cob::Pdo1Tx, Cob::Pdo2Tx, Cob::Pdo3Tx, Cob::Pdo4Tx];
+        for (index, cob) in (TPDO_COMMUNICATION..).zip(tpdos.iter()) {
+            let cob_id = get_p2p_cob_id(self.node_id, *cob) as u32;
+            self.add_pdo_objects(
+                index,
+                "TPDO",
+                &[
+                    (0x01, "COB-ID used by TPDO", ObjectValue::Unsigned32(cob_id)),
+                    (0x02, "Transmission type", ObjectValue::Unsigned8(0xFF)),
+                    (0x03, "Inhibit time", ObjectValue::Unsigned16(0)),
+                    (0x05, "Event timer", ObjectValue::Unsigned16(0)),
+                ],
+            );
+        }
+
+        self.od.add_range_validator(
+            RPDO_COMMUNICATION..=TPDO_COMMUNICATION + 2 * PDO_COUNT - 1,
+            Rc::new(RefCell::new(PdoValidator)),
+        );
+    }
+
+    fn add_pdo_objects(
+        &mut self,
+        communication_index: u16,
+        kind: &str,
+        communication: &[(u8, &str, ObjectValue)],
+    ) {
+        let index = communication_index;
+        if self.od.object_type(index).is_err() {
+            for (sub_index, name, value) in communication.iter().cloned() {
+                self.od
+                    .add_with_attributes(index, sub_index, value, named(name));
+            }
+            self.od.add_object(
+                index,
+                ObjectType::Record,
+                Some(format!("{} communication parameter", kind)),
+            );
+        }
+
+        let index = communication_index + PDO_COUNT;
+        if self.od.object_type(index).is_err() {
+            self.od.add_with_attributes(
+                index,
+                0x00,
+                ObjectValue::Unsigned8(0),
+                named("Number of mapped application objects in PDO"),
+            );
+            for sub_index in 1..=8 {
+                self.od.add_with_attributes(
+                    index,
+                    sub_index,
+                    ObjectValue::Unsigned32(0),
+                    named(&format!("Application object {}", sub_index)),
+                );
+            }
+            self.od.add_object(
+                index,
+                ObjectType::Record,
+                Some(format!("{} mapping parameter", kind)),
+            );
+        }
+    }
+
+    /// Applies the stored parameters of `area`, falling back to the defaults
+    /// when they can't be read.
+    fn load_parameters(&mut self, area: ParameterArea) {
+        if let Some(storage) = self.store_commands.borrow_mut().storage.as_mut() {
+            if storage::load_parameters(&mut self.od, storage.as_mut(), area).is_err() {
+                self.od.restore_defaults(area);
+            }
+        }
+    }
+
     fn set_nmt_state(&mut self, nmt_state: NmtState) {
+        // Values written before the node was started don't trigger TPDOs.
+        if nmt_state == NmtState::Operational && self.nmt_state != NmtState::Operational {
+            self.tpdos.reset();
+        }
         self.nmt_state = nmt_state;
     }
 
     fn reset_node(&mut self) {
         self.set_nmt_state(NmtState::Initialising);
-
-        // TODO: Reset application parameters
+        self.od.restore_defaults(ParameterArea::Application);
+        self.load_parameters(ParameterArea::Application);
 
         self.reset_communication();
     }
 
     fn reset_communication(&mut self) {
         self.set_nmt_state(NmtState::Initialising);
-
-        // TODO: Reset communication parameters
+        self.sdo_server.reset();
+        self.rpdos.reset();
+        self.od.restore_defaults(ParameterArea::Communication);
+        self.load_parameters(ParameterArea::Communication);
+        self.tpdos.reset();
 
         self.send_boot_up();
         self.set_nmt_state(NmtState::PreOperational);
@@ -87,3 +445,10 @@ impl CanOpenController {
         ));
     }
 }
+
+fn named(name: &str) -> ObjectAttributes {
+    ObjectAttributes {
+        name: Some(String::from(name)),
+        ..Default::default()
+    }
+}
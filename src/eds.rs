@@ -0,0 +1,672 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::od::{
+    AccessType, DataType, ObjectAttributes, ObjectDictionary, ObjectType, ObjectValue,
+};
+
+/// A syntax or type error in an EDS file, `line` is 1-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdsError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl EdsError {
+    pub(crate) fn new(line: usize, message: String) -> EdsError {
+        EdsError { line, message }
+    }
+}
+
+impl fmt::Display for EdsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for EdsError {}
+
+pub(crate) struct Entry {
+    pub line: usize,
+    pub value: String,
+}
+
+/// A `[name]` section, keys are stored in lower case.
+pub(crate) struct Section {
+    pub name: String,
+    pub line: usize,
+    pub entries: HashMap<String, Entry>,
+}
+
+impl Section {
+    fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.get(key)
+    }
+
+    fn require(&self, key: &str) -> Result<&Entry, EdsError> {
+        self.get(key).ok_or_else(|| {
+            EdsError::new(
+                self.line,
+                format!("[{}] is missing required key {}", self.name, key),
+            )
+        })
+    }
+}
+
+/// Builds an object dictionary from a CiA 306 electronic data sheet.
+/// `$NODEID` in values is replaced by `node_id`.
+pub fn parse(input: &str, node_id: u8) -> Result<ObjectDictionary, EdsError> {
+    build(&parse_sections(input)?, node_id)
+}
+
+/// Reads a device configuration file, returning the node ID from the
+/// `[DeviceComissioning]` section along with the configured dictionary.
+pub fn parse_dcf(input: &str) -> Result<(u8, ObjectDictionary), EdsError> {
+    let sections = parse_sections(input)?;
+    let section = sections
+        .iter()
+        .find(|section| section.name.eq_ignore_ascii_case("DeviceComissioning"))
+        .ok_or_else(|| EdsError::new(1, String::from("missing [DeviceComissioning] section")))?;
+    let entry = section.require("nodeid")?;
+    let node_id = match parse_unsigned(entry, 0)? {
+        node_id @ 1..=127 => node_id as u8,
+        _ => {
+            return Err(EdsError::new(
+                entry.line,
+                format!("invalid NodeID '{}'", entry.value),
+            ))
+        }
+    };
+    Ok((node_id, build(&sections, node_id)?))
+}
+
+/// Serializes the dictionary into a device configuration file. Current
+/// values are written as `ParameterValue` next to the defaults.
+pub fn write_dcf(od: &ObjectDictionary, node_id: u8) -> String {
+    let indices = od.indices();
+
+    let mut out = String::new();
+    out.push_str(&format!("[DeviceComissioning]\nNodeID={:#04X}\n", node_id));
+
+    let is_mandatory = |index: &u16| matches!(*index, 0x1000 | 0x1001 | 0x1018);
+    let is_manufacturer = |index: &u16| (0x2000..=0x5FFF).contains(index);
+    write_object_list(
+        &mut out,
+        "MandatoryObjects",
+        indices.iter().filter(|i| is_mandatory(i)),
+    );
+    write_object_list(
+        &mut out,
+        "OptionalObjects",
+        indices
+            .iter()
+            .filter(|i| !is_mandatory(i) && !is_manufacturer(i)),
+    );
+    write_object_list(
+        &mut out,
+        "ManufacturerObjects",
+        indices.iter().filter(|i| is_manufacturer(i)),
+    );
+
+    for index in indices.iter() {
+        let object_type = od.object_type(*index).expect("index is in the dictionary");
+        let sub_indices = od.sub_indices(*index).expect("index is in the dictionary");
+        out.push_str(&format!("\n[{:04X}]\n", index));
+        if let ObjectType::Var | ObjectType::Domain = object_type {
+            out.push_str(&format!("ObjectType={:#X}\n", object_type.code()));
+            write_entry(&mut out, od, *index, 0);
+        } else {
+            if let Ok(Some(name)) = od.object_name(*index) {
+                out.push_str(&format!("ParameterName={}\n", name));
+            }
+            out.push_str(&format!("ObjectType={:#X}\n", object_type.code()));
+            out.push_str(&format!("SubNumber={}\n", sub_indices.len()));
+            for sub_index in sub_indices {
+                out.push_str(&format!("\n[{:04X}sub{:X}]\n", index, sub_index));
+                write_entry(&mut out, od, *index, sub_index);
+            }
+        }
+    }
+
+    out
+}
+
+fn write_object_list<'a>(out: &mut String, name: &str, indices: impl Iterator<Item = &'a u16>) {
+    let indices: Vec<&u16> = indices.collect();
+    out.push_str(&format!(
+        "\n[{}]\nSupportedObjects={}\n",
+        name,
+        indices.len()
+    ));
+    for (number, index) in indices.iter().enumerate() {
+        out.push_str(&format!("{}={:#06X}\n", number + 1, index));
+    }
+}
+
+fn write_entry(out: &mut String, od: &ObjectDictionary, index: u16, sub_index: u8) {
+    let attributes = od
+        .attributes(index, sub_index)
+        .expect("entry is in the dictionary");
+    let value = od
+        .read(index, sub_index)
+        .expect("entry is in the dictionary");
+    let default_value = od
+        .default_value(index, sub_index)
+        .expect("entry is in the dictionary");
+
+    if let Some(name) = attributes.name.as_ref() {
+        out.push_str(&format!("ParameterName={}\n", name));
+    }
+    out.push_str(&format!("DataType={:#06X}\n", value.data_type().code()));
+    let access_type = match attributes.access_type {
+        AccessType::ReadOnly => "ro",
+        AccessType::WriteOnly => "wo",
+        AccessType::ReadWrite => "rw",
+        AccessType::ReadWriteRead => "rwr",
+        AccessType::ReadWriteWrite => "rww",
+        AccessType::Const => "const",
+    };
+    out.push_str(&format!("AccessType={}\n", access_type));
+    out.push_str(&format!("DefaultValue={}\n", format_value(default_value)));
+    if let Some(limit) = attributes.low_limit.as_ref() {
+        out.push_str(&format!("LowLimit={}\n", format_value(limit)));
+    }
+    if let Some(limit) = attributes.high_limit.as_ref() {
+        out.push_str(&format!("HighLimit={}\n", format_value(limit)));
+    }
+    out.push_str(&format!("PDOMapping={}\n", attributes.pdo_mappable as u8));
+    out.push_str(&format!("ParameterValue={}\n", format_value(&value)));
+}
+
+/// Formats a value the way `parse_value` reads it back.
+fn format_value(value: &ObjectValue) -> String {
+    match value {
+        ObjectValue::Boolean(v) => format!("{}", *v as u8),
+        ObjectValue::Integer8(v) => format!("{}", v),
+        ObjectValue::Integer16(v) => format!("{}", v),
+        ObjectValue::Integer24(v) | ObjectValue::Integer32(v) => format!("{}", v),
+        ObjectValue::Integer40(v)
+        | ObjectValue::Integer48(v)
+        | ObjectValue::Integer56(v)
+        | ObjectValue::Integer64(v) => format!("{}", v),
+        ObjectValue::Real32(v) => format!("{}", v),
+        ObjectValue::Real64(v) => format!("{}", v),
+        ObjectValue::VisibleString(v)
+        | ObjectValue::OctetString(v)
+        | ObjectValue::UnicodeString(v) => escape_string(v),
+        ObjectValue::Domain(v) => v.iter().map(|b| format!("{:02X}", b)).collect(),
+        _ => {
+            let raw = value
+                .to_bytes()
+                .iter()
+                .rev()
+                .fold(0u64, |raw, byte| (raw << 8) | *byte as u64);
+            format!("{:#X}", raw)
+        }
+    }
+}
+
+/// Escapes backslashes and line breaks, and the whitespace at either end
+/// of a string, which the reader would otherwise trim.
+fn escape_string(text: &str) -> String {
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len().max(start);
+    let mut out = String::new();
+    for (pos, c) in text.char_indices() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ' ' if pos < start || pos >= end => out.push_str("\\s"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverses `escape_string`, unknown escapes are kept as they are.
+fn unescape_string(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('s') => out.push(' '),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub(crate) fn build(sections: &[Section], node_id: u8) -> Result<ObjectDictionary, EdsError> {
+    let mut od = ObjectDictionary::new();
+
+    for section in sections.iter() {
+        match parse_section_name(&section.name) {
+            Some((index, None)) => parse_object(&mut od, section, index, node_id)?,
+            Some((index, Some(sub_index))) => {
+                add_entry(&mut od, section, index, sub_index, node_id)?
+            }
+            None => {}
+        }
+    }
+
+    // Values are set once every entry exists, a writable sub-index 0 can
+    // only count entries that are already there.
+    for section in sections.iter() {
+        match parse_section_name(&section.name) {
+            Some((index, None)) if section.get("parametervalue").is_some() => {
+                let is_var = matches!(
+                    od.object_type(index),
+                    Ok(ObjectType::Var) | Ok(ObjectType::Domain)
+                );
+                for sub_index in od.sub_indices(index).unwrap_or_default() {
+                    if is_var || sub_index != 0 {
+                        set_parameter_value(&mut od, section, index, sub_index, node_id)?;
+                    }
+                }
+            }
+            Some((index, Some(sub_index))) => {
+                set_parameter_value(&mut od, section, index, sub_index, node_id)?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(od)
+}
+
+fn parse_sections(input: &str) -> Result<Vec<Section>, EdsError> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (number, raw_line) in input.lines().enumerate() {
+        let line = number + 1;
+        let text = raw_line.trim_start_matches('\u{feff}').trim();
+        if text.is_empty() || text.starts_with(';') {
+            continue;
+        }
+
+        if text.starts_with('[') {
+            if !text.ends_with(']') {
+                return Err(EdsError::new(
+                    line,
+                    format!("unterminated section header '{}'", text),
+                ));
+            }
+            sections.push(Section {
+                name: text[1..text.len() - 1].trim().to_string(),
+                line,
+                entries: HashMap::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = match text.find('=') {
+            Some(pos) => (text[..pos].trim(), text[pos + 1..].trim()),
+            None => {
+                return Err(EdsError::new(
+                    line,
+                    format!("expected 'key=value', found '{}'", text),
+                ))
+            }
+        };
+        let section = match sections.last_mut() {
+            Some(section) => section,
+            None => {
+                return Err(EdsError::new(
+                    line,
+                    format!("key '{}' outside of a section", key),
+                ))
+            }
+        };
+        section.entries.insert(
+            key.to_lowercase(),
+            Entry {
+                line,
+                value: value.to_string(),
+            },
+        );
+    }
+
+    Ok(sections)
+}
+
+/// Matches `1018` and `1018sub1` style section names.
+fn parse_section_name(name: &str) -> Option<(u16, Option<u8>)> {
+    if name.len() < 4 || !name.is_char_boundary(4) {
+        return None;
+    }
+    let index = parse_hex(&name[..4])?;
+    let rest = name[4..].to_lowercase();
+    if rest.is_empty() {
+        Some((index as u16, None))
+    } else if let Some(sub_index) = rest.strip_prefix("sub") {
+        match parse_hex(sub_index)? {
+            sub_index if sub_index <= 0xFF => Some((index as u16, Some(sub_index as u8))),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    if text.is_empty() || text.len() > 4 || !text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(text, 16).ok()
+}
+
+fn parse_object(
+    od: &mut ObjectDictionary,
+    section: &Section,
+    index: u16,
+    node_id: u8,
+) -> Result<(), EdsError> {
+    let object_type = match section.get("objecttype") {
+        Some(entry) => match parse_unsigned(entry, node_id)? {
+            code if code <= 0xFF => ObjectType::from_code(code as u8),
+            _ => None,
+        },
+        None => Some(ObjectType::Var),
+    };
+
+    match object_type {
+        Some(object_type @ ObjectType::Var) | Some(object_type @ ObjectType::Domain) => {
+            add_entry(od, section, index, 0, node_id)?;
+            od.add_object(index, object_type, parameter_name(section));
+            Ok(())
+        }
+        Some(object_type) => {
+            od.add_object(index, object_type, parameter_name(section));
+
+            let count = match section.get("compactsubobj") {
+                Some(entry) => parse_unsigned(entry, node_id)?,
+                None => 0,
+            };
+            if count > 0xFE {
+                let entry = section.require("compactsubobj")?;
+                return Err(EdsError::new(
+                    entry.line,
+                    format!("CompactSubObj {} out of range", count),
+                ));
+            }
+            if count > 0 {
+                add_compact_entries(od, section, index, count as u8, node_id)?;
+            }
+            Ok(())
+        }
+        // Type definitions describe no entries of their own.
+        None => Ok(()),
+    }
+}
+
+/// Expands an array described with `CompactSubObj`, every sub-index shares the
+/// attributes of the object section.
+fn add_compact_entries(
+    od: &mut ObjectDictionary,
+    section: &Section,
+    index: u16,
+    count: u8,
+    node_id: u8,
+) -> Result<(), EdsError> {
+    let name = parameter_name(section);
+    od.add_with_attributes(
+        index,
+        0,
+        ObjectValue::Unsigned8(count),
+        ObjectAttributes {
+            access_type: AccessType::ReadOnly,
+            name: Some(String::from("Number of entries")),
+            ..Default::default()
+        },
+    );
+    for sub_index in 1..=count {
+        let (value, mut attributes) = parse_entry(section, node_id)?;
+        attributes.name = name.as_ref().map(|n| format!("{}{}", n, sub_index));
+        od.add_with_attributes(index, sub_index, value, attributes);
+    }
+    Ok(())
+}
+
+fn add_entry(
+    od: &mut ObjectDictionary,
+    section: &Section,
+    index: u16,
+    sub_index: u8,
+    node_id: u8,
+) -> Result<(), EdsError> {
+    let (value, attributes) = parse_entry(section, node_id)?;
+    od.add_with_attributes(index, sub_index, value, attributes);
+    Ok(())
+}
+
+/// A DCF `ParameterValue` overrides the current value, the `DefaultValue`
+/// is kept for restoring defaults.
+fn set_parameter_value(
+    od: &mut ObjectDictionary,
+    section: &Section,
+    index: u16,
+    sub_index: u8,
+    node_id: u8,
+) -> Result<(), EdsError> {
+    let entry = match section.get("parametervalue") {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+    let data_type = od
+        .data_type(index, sub_index)
+        .expect("entry was added in the first pass");
+    let value = parse_value(data_type, entry, node_id)?;
+    od.write(index, sub_index, value)
+        .map_err(|error| EdsError::new(entry.line, format!("invalid ParameterValue: {}", error)))
+}
+
+fn parameter_name(section: &Section) -> Option<String> {
+    section
+        .get("parametername")
+        .map(|entry| entry.value.clone())
+}
+
+fn parse_entry(
+    section: &Section,
+    node_id: u8,
+) -> Result<(ObjectValue, ObjectAttributes), EdsError> {
+    let entry = section.require("datatype")?;
+    let code = parse_unsigned(entry, node_id)?;
+    let data_type = match DataType::from_code(code as u16) {
+        Some(data_type) if code <= 0xFFFF => data_type,
+        _ => {
+            return Err(EdsError::new(
+                entry.line,
+                format!("unsupported DataType {:#06X}", code),
+            ))
+        }
+    };
+
+    let entry = section.require("accesstype")?;
+    let access_type = match entry.value.to_lowercase().as_str() {
+        "ro" => AccessType::ReadOnly,
+        "wo" => AccessType::WriteOnly,
+        "rw" => AccessType::ReadWrite,
+        "rwr" => AccessType::ReadWriteRead,
+        "rww" => AccessType::ReadWriteWrite,
+        "const" => AccessType::Const,
+        _ => {
+            return Err(EdsError::new(
+                entry.line,
+                format!("invalid AccessType '{}'", entry.value),
+            ))
+        }
+    };
+
+    let pdo_mappable = match section.get("pdomapping") {
+        Some(entry) => parse_unsigned(entry, node_id)? != 0,
+        None => false,
+    };
+
+    let value = match section.get("defaultvalue") {
+        Some(entry) => parse_value(data_type, entry, node_id)?,
+        None => empty_value(data_type),
+    };
+    let low_limit = parse_limit(data_type, section.get("lowlimit"), node_id)?;
+    let high_limit = parse_limit(data_type, section.get("highlimit"), node_id)?;
+
+    Ok((
+        value,
+        ObjectAttributes {
+            access_type,
+            pdo_mappable,
+            name: parameter_name(section),
+            low_limit,
+            high_limit,
+        },
+    ))
+}
+
+fn parse_limit(
+    data_type: DataType,
+    entry: Option<&Entry>,
+    node_id: u8,
+) -> Result<Option<ObjectValue>, EdsError> {
+    match entry {
+        Some(entry) if !entry.value.is_empty() => Ok(Some(parse_value(data_type, entry, node_id)?)),
+        _ => Ok(None),
+    }
+}
+
+/// The value of an entry without a `DefaultValue`, zero or empty.
+fn empty_value(data_type: DataType) -> ObjectValue {
+    let size = data_type.size().unwrap_or(0);
+    ObjectValue::from_bytes(data_type, &vec![0; size]).expect("zeroes are valid for every type")
+}
+
+fn parse_value(data_type: DataType, entry: &Entry, node_id: u8) -> Result<ObjectValue, EdsError> {
+    let text = entry.value.as_str();
+    let invalid = || {
+        EdsError::new(
+            entry.line,
+            format!("invalid value '{}' for {:?}", text, data_type),
+        )
+    };
+
+    match data_type {
+        DataType::VisibleString => Ok(ObjectValue::VisibleString(unescape_string(text))),
+        DataType::OctetString => Ok(ObjectValue::OctetString(unescape_string(text))),
+        DataType::UnicodeString => Ok(ObjectValue::UnicodeString(unescape_string(text))),
+        DataType::Real32 => text.parse().map(ObjectValue::Real32).map_err(|_| invalid()),
+        DataType::Real64 => text.parse().map(ObjectValue::Real64).map_err(|_| invalid()),
+        DataType::Domain => parse_domain(text)
+            .map(ObjectValue::Domain)
+            .ok_or_else(invalid),
+        _ if text.is_empty() => Ok(empty_value(data_type)),
+        _ => {
+            let value = parse_integer(text, node_id).ok_or_else(invalid)?;
+            let bits = 8 * data_type.size().expect("numeric types have a size") as u32;
+            let (min, max) = match data_type {
+                DataType::Boolean => (0, 1),
+                DataType::Integer8
+                | DataType::Integer16
+                | DataType::Integer24
+                | DataType::Integer32
+                | DataType::Integer40
+                | DataType::Integer48
+                | DataType::Integer56
+                | DataType::Integer64 => (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1),
+                _ => (0, (1i128 << bits) - 1),
+            };
+            // Signed values are often written as two's complement hex, such
+            // as 0xFFFF for -1 in an INTEGER16.
+            let max = if min < 0 && is_hex(text) {
+                (1i128 << bits) - 1
+            } else {
+                max
+            };
+            if value < min || value > max {
+                return Err(EdsError::new(
+                    entry.line,
+                    format!("value '{}' out of range for {:?}", text, data_type),
+                ));
+            }
+            let size = (bits / 8) as usize;
+            ObjectValue::from_bytes(data_type, &value.to_le_bytes()[..size]).map_err(|_| invalid())
+        }
+    }
+}
+
+fn is_hex(text: &str) -> bool {
+    text.starts_with("0x") || text.starts_with("0X")
+}
+
+fn parse_unsigned(entry: &Entry, node_id: u8) -> Result<u32, EdsError> {
+    match parse_integer(&entry.value, node_id) {
+        Some(value) if (0..=u32::MAX as i128).contains(&value) => Ok(value as u32),
+        _ => Err(EdsError::new(
+            entry.line,
+            format!("invalid number '{}'", entry.value),
+        )),
+    }
+}
+
+/// Integers are decimal, `0x` hexadecimal or `0` prefixed octal, optionally
+/// added to `$NODEID`.
+fn parse_integer(text: &str, node_id: u8) -> Option<i128> {
+    text.split('+').try_fold(0i128, |sum, term| {
+        let term = term.trim();
+        let value = if term.eq_ignore_ascii_case("$nodeid") {
+            node_id as i128
+        } else {
+            parse_number(term)?
+        };
+        sum.checked_add(value)
+    })
+}
+
+fn parse_number(text: &str) -> Option<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let (radix, digits) = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        (16, hex)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (8, &digits[1..])
+    } else {
+        (10, digits)
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let value = i128::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// DOMAIN defaults are written as a string of hexadecimal bytes.
+fn parse_domain(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if digits.len() % 2 != 0 || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
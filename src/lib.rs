@@ -1,5 +1,10 @@
 pub mod cob;
+pub mod codegen;
+pub mod concise_dcf;
 pub mod controller;
+pub mod eds;
 pub mod message;
 pub mod od;
 mod service;
+pub mod storage;
+pub mod xdd;
@@ -2,22 +2,43 @@ use crate::cob::{get_broadcast_cob_id, get_p2p_cob_id, Cob};
 
 pub struct CanMessage {
     can_id: u16,
+    rtr: bool,
     data: Vec<u8>,
 }
 
 impl CanMessage {
     pub fn from_can_id(can_id: u16, data: Vec<u8>) -> CanMessage {
-        CanMessage { can_id, data }
+        CanMessage {
+            can_id,
+            rtr: false,
+            data,
+        }
     }
 
     pub fn from_cob(cob: Cob, data: Vec<u8>) -> CanMessage {
-        let can_id = get_broadcast_cob_id(cob);
-        CanMessage { can_id, data }
+        CanMessage::from_can_id(get_broadcast_cob_id(cob), data)
     }
 
     pub fn from_node_id(node_id: u8, cob: Cob, data: Vec<u8>) -> CanMessage {
-        let can_id = get_p2p_cob_id(node_id, cob);
-        CanMessage { can_id, data }
+        CanMessage::from_can_id(get_p2p_cob_id(node_id, cob), data)
+    }
+
+    /// A remote transmission request (RTR) frame, asking the producer of
+    /// `can_id` to transmit it. Remote frames carry no data.
+    pub fn remote_request(can_id: u16) -> CanMessage {
+        CanMessage {
+            can_id,
+            rtr: true,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn can_id(&self) -> u16 {
+        self.can_id
+    }
+
+    pub fn is_rtr(&self) -> bool {
+        self.rtr
     }
 
     pub fn node_id(&self) -> u8 {
@@ -66,6 +87,21 @@ mod tests {
     use crate::cob::Cob;
     use crate::message::CanMessage;
 
+    #[test]
+    fn test_get_can_id() {
+        let msg = CanMessage::from_can_id(0x1B4, Vec::new());
+        assert_eq!(msg.can_id(), 0x1B4);
+    }
+
+    #[test]
+    fn test_remote_request() {
+        let msg = CanMessage::remote_request(0x1B4);
+        assert!(msg.is_rtr());
+        assert_eq!(msg.can_id(), 0x1B4);
+        assert_eq!(msg.data_length(), 0);
+        assert!(!CanMessage::from_can_id(0x1B4, Vec::new()).is_rtr());
+    }
+
     #[test]
     fn test_get_node_id() {
         let msg = CanMessage::from_can_id(0x1B4, Vec::new());
@@ -1,53 +1,679 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::rc::Rc;
 use std::string::String;
 
-#[derive(Hash, Eq, PartialEq)]
-struct ObjectKey {
-    index: u16,
-    sub_index: u8,
+/// Milliseconds after midnight and days since January 1, 1984.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeOfDay {
+    pub milliseconds: u32,
+    pub days: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeDifference {
+    pub milliseconds: u32,
+    pub days: u16,
+}
+
+/// CiA 301 data types with their object dictionary type codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Boolean,
+    Integer8,
+    Integer16,
+    Integer32,
+    Unsigned8,
+    Unsigned16,
+    Unsigned32,
+    Real32,
+    VisibleString,
+    OctetString,
+    UnicodeString,
+    TimeOfDay,
+    TimeDifference,
+    Domain,
+    Integer24,
+    Real64,
+    Integer40,
+    Integer48,
+    Integer56,
+    Integer64,
+    Unsigned24,
+    Unsigned40,
+    Unsigned48,
+    Unsigned56,
+    Unsigned64,
+}
+
+impl DataType {
+    pub fn code(&self) -> u16 {
+        match self {
+            DataType::Boolean => 0x0001,
+            DataType::Integer8 => 0x0002,
+            DataType::Integer16 => 0x0003,
+            DataType::Integer32 => 0x0004,
+            DataType::Unsigned8 => 0x0005,
+            DataType::Unsigned16 => 0x0006,
+            DataType::Unsigned32 => 0x0007,
+            DataType::Real32 => 0x0008,
+            DataType::VisibleString => 0x0009,
+            DataType::OctetString => 0x000A,
+            DataType::UnicodeString => 0x000B,
+            DataType::TimeOfDay => 0x000C,
+            DataType::TimeDifference => 0x000D,
+            DataType::Domain => 0x000F,
+            DataType::Integer24 => 0x0010,
+            DataType::Real64 => 0x0011,
+            DataType::Integer40 => 0x0012,
+            DataType::Integer48 => 0x0013,
+            DataType::Integer56 => 0x0014,
+            DataType::Integer64 => 0x0015,
+            DataType::Unsigned24 => 0x0016,
+            DataType::Unsigned40 => 0x0018,
+            DataType::Unsigned48 => 0x0019,
+            DataType::Unsigned56 => 0x001A,
+            DataType::Unsigned64 => 0x001B,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<DataType> {
+        match code {
+            0x0001 => Some(DataType::Boolean),
+            0x0002 => Some(DataType::Integer8),
+            0x0003 => Some(DataType::Integer16),
+            0x0004 => Some(DataType::Integer32),
+            0x0005 => Some(DataType::Unsigned8),
+            0x0006 => Some(DataType::Unsigned16),
+            0x0007 => Some(DataType::Unsigned32),
+            0x0008 => Some(DataType::Real32),
+            0x0009 => Some(DataType::VisibleString),
+            0x000A => Some(DataType::OctetString),
+            0x000B => Some(DataType::UnicodeString),
+            0x000C => Some(DataType::TimeOfDay),
+            0x000D => Some(DataType::TimeDifference),
+            0x000F => Some(DataType::Domain),
+            0x0010 => Some(DataType::Integer24),
+            0x0011 => Some(DataType::Real64),
+            0x0012 => Some(DataType::Integer40),
+            0x0013 => Some(DataType::Integer48),
+            0x0014 => Some(DataType::Integer56),
+            0x0015 => Some(DataType::Integer64),
+            0x0016 => Some(DataType::Unsigned24),
+            0x0018 => Some(DataType::Unsigned40),
+            0x0019 => Some(DataType::Unsigned48),
+            0x001A => Some(DataType::Unsigned56),
+            0x001B => Some(DataType::Unsigned64),
+            _ => None,
+        }
+    }
+
+    /// Encoded size in bytes, `None` for the variable length types.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            DataType::Boolean | DataType::Integer8 | DataType::Unsigned8 => Some(1),
+            DataType::Integer16 | DataType::Unsigned16 => Some(2),
+            DataType::Integer24 | DataType::Unsigned24 => Some(3),
+            DataType::Integer32 | DataType::Unsigned32 | DataType::Real32 => Some(4),
+            DataType::Integer40 | DataType::Unsigned40 => Some(5),
+            DataType::Integer48
+            | DataType::Unsigned48
+            | DataType::TimeOfDay
+            | DataType::TimeDifference => Some(6),
+            DataType::Integer56 | DataType::Unsigned56 => Some(7),
+            DataType::Integer64 | DataType::Unsigned64 | DataType::Real64 => Some(8),
+            DataType::VisibleString
+            | DataType::OctetString
+            | DataType::UnicodeString
+            | DataType::Domain => None,
+        }
+    }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum ObjectValue {
     Boolean(bool),
     Integer8(i8),
     Integer16(i16),
+    Integer24(i32),
     Integer32(i32),
+    Integer40(i64),
+    Integer48(i64),
+    Integer56(i64),
+    Integer64(i64),
     Unsigned8(u8),
     Unsigned16(u16),
+    Unsigned24(u32),
     Unsigned32(u32),
+    Unsigned40(u64),
+    Unsigned48(u64),
+    Unsigned56(u64),
+    Unsigned64(u64),
     Real32(f32),
+    Real64(f64),
     VisibleString(String),
     OctetString(String),
     UnicodeString(String),
+    TimeOfDay(TimeOfDay),
+    TimeDifference(TimeDifference),
+    Domain(Vec<u8>),
+}
+
+impl ObjectValue {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ObjectValue::Boolean(_) => DataType::Boolean,
+            ObjectValue::Integer8(_) => DataType::Integer8,
+            ObjectValue::Integer16(_) => DataType::Integer16,
+            ObjectValue::Integer24(_) => DataType::Integer24,
+            ObjectValue::Integer32(_) => DataType::Integer32,
+            ObjectValue::Integer40(_) => DataType::Integer40,
+            ObjectValue::Integer48(_) => DataType::Integer48,
+            ObjectValue::Integer56(_) => DataType::Integer56,
+            ObjectValue::Integer64(_) => DataType::Integer64,
+            ObjectValue::Unsigned8(_) => DataType::Unsigned8,
+            ObjectValue::Unsigned16(_) => DataType::Unsigned16,
+            ObjectValue::Unsigned24(_) => DataType::Unsigned24,
+            ObjectValue::Unsigned32(_) => DataType::Unsigned32,
+            ObjectValue::Unsigned40(_) => DataType::Unsigned40,
+            ObjectValue::Unsigned48(_) => DataType::Unsigned48,
+            ObjectValue::Unsigned56(_) => DataType::Unsigned56,
+            ObjectValue::Unsigned64(_) => DataType::Unsigned64,
+            ObjectValue::Real32(_) => DataType::Real32,
+            ObjectValue::Real64(_) => DataType::Real64,
+            ObjectValue::VisibleString(_) => DataType::VisibleString,
+            ObjectValue::OctetString(_) => DataType::OctetString,
+            ObjectValue::UnicodeString(_) => DataType::UnicodeString,
+            ObjectValue::TimeOfDay(_) => DataType::TimeOfDay,
+            ObjectValue::TimeDifference(_) => DataType::TimeDifference,
+            ObjectValue::Domain(_) => DataType::Domain,
+        }
+    }
+
+    /// Little-endian encoding as transferred on the bus.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ObjectValue::Boolean(v) => vec![*v as u8],
+            ObjectValue::Integer8(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Integer16(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Integer24(v) => v.to_le_bytes()[..3].to_vec(),
+            ObjectValue::Integer32(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Integer40(v) => v.to_le_bytes()[..5].to_vec(),
+            ObjectValue::Integer48(v) => v.to_le_bytes()[..6].to_vec(),
+            ObjectValue::Integer56(v) => v.to_le_bytes()[..7].to_vec(),
+            ObjectValue::Integer64(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Unsigned8(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Unsigned16(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Unsigned24(v) => v.to_le_bytes()[..3].to_vec(),
+            ObjectValue::Unsigned32(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Unsigned40(v) => v.to_le_bytes()[..5].to_vec(),
+            ObjectValue::Unsigned48(v) => v.to_le_bytes()[..6].to_vec(),
+            ObjectValue::Unsigned56(v) => v.to_le_bytes()[..7].to_vec(),
+            ObjectValue::Unsigned64(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Real32(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::Real64(v) => v.to_le_bytes().to_vec(),
+            ObjectValue::VisibleString(v) => v.as_bytes().to_vec(),
+            ObjectValue::OctetString(v) => v.as_bytes().to_vec(),
+            ObjectValue::UnicodeString(v) => v.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            ObjectValue::TimeOfDay(v) => encode_time(v.milliseconds, v.days),
+            ObjectValue::TimeDifference(v) => encode_time(v.milliseconds, v.days),
+            ObjectValue::Domain(v) => v.clone(),
+        }
+    }
+
+    /// Decodes a little-endian value of `data_type`. Fixed size types require
+    /// an exact length match.
+    pub fn from_bytes(
+        data_type: DataType,
+        data: &[u8],
+    ) -> Result<ObjectValue, ObjectDictionaryError> {
+        if let Some(size) = data_type.size() {
+            match data.len().cmp(&size) {
+                Ordering::Greater => return Err(ObjectDictionaryError::LengthTooHigh),
+                Ordering::Less => return Err(ObjectDictionaryError::LengthTooLow),
+                Ordering::Equal => {}
+            }
+        }
+
+        Ok(match data_type {
+            DataType::Boolean => ObjectValue::Boolean(data[0] != 0),
+            DataType::Integer8 => ObjectValue::Integer8(data[0] as i8),
+            DataType::Integer16 => ObjectValue::Integer16(signed(data) as i16),
+            DataType::Integer24 => ObjectValue::Integer24(signed(data) as i32),
+            DataType::Integer32 => ObjectValue::Integer32(signed(data) as i32),
+            DataType::Integer40 => ObjectValue::Integer40(signed(data)),
+            DataType::Integer48 => ObjectValue::Integer48(signed(data)),
+            DataType::Integer56 => ObjectValue::Integer56(signed(data)),
+            DataType::Integer64 => ObjectValue::Integer64(signed(data)),
+            DataType::Unsigned8 => ObjectValue::Unsigned8(data[0]),
+            DataType::Unsigned16 => ObjectValue::Unsigned16(unsigned(data) as u16),
+            DataType::Unsigned24 => ObjectValue::Unsigned24(unsigned(data) as u32),
+            DataType::Unsigned32 => ObjectValue::Unsigned32(unsigned(data) as u32),
+            DataType::Unsigned40 => ObjectValue::Unsigned40(unsigned(data)),
+            DataType::Unsigned48 => ObjectValue::Unsigned48(unsigned(data)),
+            DataType::Unsigned56 => ObjectValue::Unsigned56(unsigned(data)),
+            DataType::Unsigned64 => ObjectValue::Unsigned64(unsigned(data)),
+            DataType::Real32 => ObjectValue::Real32(f32::from_bits(unsigned(data) as u32)),
+            DataType::Real64 => ObjectValue::Real64(f64::from_bits(unsigned(data))),
+            DataType::VisibleString => ObjectValue::VisibleString(
+                String::from_utf8(data.to_vec())
+                    .map_err(|_| ObjectDictionaryError::DataTypeMismatch)?,
+            ),
+            DataType::OctetString => ObjectValue::OctetString(
+                String::from_utf8(data.to_vec())
+                    .map_err(|_| ObjectDictionaryError::DataTypeMismatch)?,
+            ),
+            DataType::UnicodeString => {
+                if data.len() % 2 != 0 {
+                    return Err(ObjectDictionaryError::DataTypeMismatch);
+                }
+                let units: Vec<u16> = data
+                    .chunks(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                ObjectValue::UnicodeString(
+                    String::from_utf16(&units)
+                        .map_err(|_| ObjectDictionaryError::DataTypeMismatch)?,
+                )
+            }
+            DataType::TimeOfDay => {
+                let (milliseconds, days) = decode_time(data);
+                ObjectValue::TimeOfDay(TimeOfDay { milliseconds, days })
+            }
+            DataType::TimeDifference => {
+                let (milliseconds, days) = decode_time(data);
+                ObjectValue::TimeDifference(TimeDifference { milliseconds, days })
+            }
+            DataType::Domain => ObjectValue::Domain(data.to_vec()),
+        })
+    }
+
+    fn is_same_type(&self, other: &ObjectValue) -> bool {
+        self.data_type() == other.data_type()
+    }
+
+    /// Orders two numeric values of the same type, `None` for anything else.
+    fn compare(&self, other: &ObjectValue) -> Option<Ordering> {
+        match (self, other) {
+            (ObjectValue::Integer8(a), ObjectValue::Integer8(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer16(a), ObjectValue::Integer16(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer24(a), ObjectValue::Integer24(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer32(a), ObjectValue::Integer32(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer40(a), ObjectValue::Integer40(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer48(a), ObjectValue::Integer48(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer56(a), ObjectValue::Integer56(b)) => a.partial_cmp(b),
+            (ObjectValue::Integer64(a), ObjectValue::Integer64(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned8(a), ObjectValue::Unsigned8(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned16(a), ObjectValue::Unsigned16(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned24(a), ObjectValue::Unsigned24(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned32(a), ObjectValue::Unsigned32(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned40(a), ObjectValue::Unsigned40(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned48(a), ObjectValue::Unsigned48(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned56(a), ObjectValue::Unsigned56(b)) => a.partial_cmp(b),
+            (ObjectValue::Unsigned64(a), ObjectValue::Unsigned64(b)) => a.partial_cmp(b),
+            (ObjectValue::Real32(a), ObjectValue::Real32(b)) => a.partial_cmp(b),
+            (ObjectValue::Real64(a), ObjectValue::Real64(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+/// TIME_OF_DAY and TIME_DIFFERENCE share the same layout, 28 bits of
+/// milliseconds followed by 4 reserved bits and 16 bits of days.
+fn encode_time(milliseconds: u32, days: u16) -> Vec<u8> {
+    let mut data = (milliseconds & 0x0FFF_FFFF).to_le_bytes().to_vec();
+    data.extend_from_slice(&days.to_le_bytes());
+    data
+}
+
+fn decode_time(data: &[u8]) -> (u32, u16) {
+    (
+        unsigned(&data[..4]) as u32 & 0x0FFF_FFFF,
+        unsigned(&data[4..]) as u16,
+    )
+}
+
+/// Little-endian unsigned integer of up to 8 bytes.
+fn unsigned(data: &[u8]) -> u64 {
+    data.iter()
+        .rev()
+        .fold(0u64, |value, byte| (value << 8) | *byte as u64)
+}
+
+/// Little-endian two's complement integer of up to 8 bytes.
+fn signed(data: &[u8]) -> i64 {
+    let shift = 64 - 8 * data.len() as u32;
+    ((unsigned(data) << shift) as i64) >> shift
+}
+
+/// The parts of the dictionary that are reset to their defaults by the NMT
+/// reset commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParameterArea {
+    /// Communication profile area, 0x1000 - 0x1FFF.
+    Communication,
+    /// Manufacturer specific and device profile areas, 0x2000 - 0x9FFF.
+    Application,
+}
+
+impl ParameterArea {
+    pub fn contains(&self, index: u16) -> bool {
+        match self {
+            ParameterArea::Communication => (0x1000..=0x1FFF).contains(&index),
+            ParameterArea::Application => (0x2000..=0x9FFF).contains(&index),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectDictionaryError {
+    ObjectDoesNotExist,
+    SubIndexDoesNotExist,
+    DataTypeMismatch,
+    ReadOnly,
+    WriteOnly,
+    ValueRangeExceeded,
+    ValueTooHigh,
+    ValueTooLow,
+    LengthTooHigh,
+    LengthTooLow,
+    HardwareError,
+    CannotTransfer,
+    LocalControl,
+    DeviceState,
+    NotMappable,
+    PdoLengthExceeded,
+}
+
+impl ObjectDictionaryError {
+    /// The CiA 301 SDO abort code corresponding to the error.
+    pub fn abort_code(&self) -> u32 {
+        match self {
+            ObjectDictionaryError::ObjectDoesNotExist => 0x0602_0000,
+            ObjectDictionaryError::SubIndexDoesNotExist => 0x0609_0011,
+            ObjectDictionaryError::DataTypeMismatch => 0x0607_0010,
+            ObjectDictionaryError::ReadOnly => 0x0601_0002,
+            ObjectDictionaryError::WriteOnly => 0x0601_0001,
+            ObjectDictionaryError::ValueRangeExceeded => 0x0609_0030,
+            ObjectDictionaryError::ValueTooHigh => 0x0609_0031,
+            ObjectDictionaryError::ValueTooLow => 0x0609_0032,
+            ObjectDictionaryError::LengthTooHigh => 0x0607_0012,
+            ObjectDictionaryError::LengthTooLow => 0x0607_0013,
+            ObjectDictionaryError::HardwareError => 0x0606_0000,
+            ObjectDictionaryError::CannotTransfer => 0x0800_0020,
+            ObjectDictionaryError::LocalControl => 0x0800_0021,
+            ObjectDictionaryError::DeviceState => 0x0800_0022,
+            ObjectDictionaryError::NotMappable => 0x0604_0041,
+            ObjectDictionaryError::PdoLengthExceeded => 0x0604_0042,
+        }
+    }
+}
+
+impl fmt::Display for ObjectDictionaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ObjectDictionaryError::ObjectDoesNotExist => "object does not exist",
+            ObjectDictionaryError::SubIndexDoesNotExist => "sub-index does not exist",
+            ObjectDictionaryError::DataTypeMismatch => "data type does not match",
+            ObjectDictionaryError::ReadOnly => "attempt to write a read only object",
+            ObjectDictionaryError::WriteOnly => "attempt to read a write only object",
+            ObjectDictionaryError::ValueRangeExceeded => "value range of parameter exceeded",
+            ObjectDictionaryError::ValueTooHigh => "value of parameter written too high",
+            ObjectDictionaryError::ValueTooLow => "value of parameter written too low",
+            ObjectDictionaryError::LengthTooHigh => "length of service parameter too high",
+            ObjectDictionaryError::LengthTooLow => "length of service parameter too low",
+            ObjectDictionaryError::HardwareError => "access failed due to a hardware error",
+            ObjectDictionaryError::CannotTransfer => {
+                "data cannot be transferred or stored to the application"
+            }
+            ObjectDictionaryError::LocalControl => {
+                "data cannot be transferred or stored because of local control"
+            }
+            ObjectDictionaryError::DeviceState => {
+                "data cannot be transferred or stored because of the present device state"
+            }
+            ObjectDictionaryError::NotMappable => "object cannot be mapped to the PDO",
+            ObjectDictionaryError::PdoLengthExceeded => {
+                "number and length of objects to be mapped exceeds PDO length"
+            }
+        };
+        write!(
+            f,
+            "{} (abort code {:#010X})",
+            description,
+            self.abort_code()
+        )
+    }
+}
+
+impl std::error::Error for ObjectDictionaryError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessType {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+    /// Read/write, mappable into transmit PDOs (process input).
+    ReadWriteRead,
+    /// Read/write, mappable into receive PDOs (process output).
+    ReadWriteWrite,
+    Const,
+}
+
+impl AccessType {
+    pub fn is_readable(&self) -> bool {
+        *self != AccessType::WriteOnly
+    }
+
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, AccessType::ReadOnly | AccessType::Const)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectAttributes {
+    pub access_type: AccessType,
+    pub pdo_mappable: bool,
+    pub name: Option<String>,
+    pub low_limit: Option<ObjectValue>,
+    pub high_limit: Option<ObjectValue>,
+}
+
+impl Default for ObjectAttributes {
+    fn default() -> Self {
+        ObjectAttributes {
+            access_type: AccessType::ReadWrite,
+            pdo_mappable: false,
+            name: None,
+            low_limit: None,
+            high_limit: None,
+        }
+    }
+}
+
+impl ObjectAttributes {
+    fn check_limits(&self, value: &ObjectValue) -> Result<(), ObjectDictionaryError> {
+        if let Some(Ordering::Greater) = self.high_limit.as_ref().and_then(|l| value.compare(l)) {
+            return Err(ObjectDictionaryError::ValueTooHigh);
+        }
+        if let Some(Ordering::Less) = self.low_limit.as_ref().and_then(|l| value.compare(l)) {
+            return Err(ObjectDictionaryError::ValueTooLow);
+        }
+        Ok(())
+    }
 }
 
 pub trait ObjectSubscriber {
     fn object_updated(&mut self, index: u16, sub_index: u8, value: &ObjectValue);
 }
 
-struct Object {
+/// Carries out a command written to an entry by a remote node, such as the
+/// signature written to store parameters. The written value is not stored,
+/// an error is returned to the writer.
+pub trait ObjectCommand {
+    fn execute(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError>;
+}
+
+/// Checks a value before a remote write stores it, an error rejects the write
+/// and is reported as the SDO abort code. Subscribers are only notified of
+/// accepted writes. `od` is the dictionary as it is before the write.
+pub trait ObjectValidator {
+    fn validate_write(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError>;
+}
+
+/// Supplies the value of an entry at the moment it is read, for values such
+/// as sensor readings or counters that are computed on demand. The provided
+/// value must have the data type of the entry.
+pub trait ObjectProvider {
+    fn provide_value(&mut self, index: u16, sub_index: u8) -> ObjectValue;
+}
+
+/// The read, write and subscribe surface shared by `ObjectDictionary` and
+/// dictionaries generated with `codegen::generate`.
+pub trait ObjectAccess {
+    fn read(&self, index: u16, sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError>;
+
+    fn write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError>;
+
+    fn subscribe(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError>;
+}
+
+/// CiA 301 object codes describing how an index is structured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectType {
+    Domain,
+    Var,
+    Array,
+    Record,
+}
+
+impl ObjectType {
+    pub fn code(&self) -> u8 {
+        match self {
+            ObjectType::Domain => 0x2,
+            ObjectType::Var => 0x7,
+            ObjectType::Array => 0x8,
+            ObjectType::Record => 0x9,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<ObjectType> {
+        match code {
+            0x2 => Some(ObjectType::Domain),
+            0x7 => Some(ObjectType::Var),
+            0x8 => Some(ObjectType::Array),
+            0x9 => Some(ObjectType::Record),
+            _ => None,
+        }
+    }
+}
+
+struct Entry {
     index: u16,
     sub_index: u8,
     value: ObjectValue,
+    default_value: ObjectValue,
+    attributes: ObjectAttributes,
     subscribers: Vec<Rc<RefCell<dyn ObjectSubscriber>>>,
+    validators: Vec<Rc<RefCell<dyn ObjectValidator>>>,
+    command: Option<Rc<RefCell<dyn ObjectCommand>>>,
+    provider: Option<Rc<RefCell<dyn ObjectProvider>>>,
 }
 
-impl Object {
-    pub fn write(&mut self, value: ObjectValue) {
-        if std::mem::discriminant(&self.value) == std::mem::discriminant(&value) {
-            self.value = value;
-            for subscriber in self.subscribers.iter_mut() {
-                subscriber
-                    .borrow_mut()
-                    .object_updated(self.index, self.sub_index, &self.value);
-            }
+impl Entry {
+    fn new(index: u16, sub_index: u8, value: ObjectValue, attributes: ObjectAttributes) -> Entry {
+        Entry {
+            index,
+            sub_index,
+            default_value: value.clone(),
+            value,
+            attributes,
+            subscribers: Vec::new(),
+            validators: Vec::new(),
+            command: None,
+            provider: None,
+        }
+    }
+
+    pub fn write(&mut self, value: ObjectValue) -> Result<(), ObjectDictionaryError> {
+        if !self.value.is_same_type(&value) {
+            return Err(ObjectDictionaryError::DataTypeMismatch);
+        }
+        self.attributes.check_limits(&value)?;
+
+        self.value = value;
+        self.notify_subscribers();
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        od: &ObjectDictionary,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        if !self.value.is_same_type(value) {
+            return Err(ObjectDictionaryError::DataTypeMismatch);
+        }
+        self.attributes.check_limits(value)?;
+        for validator in self.validators.iter() {
+            validator
+                .borrow_mut()
+                .validate_write(od, self.index, self.sub_index, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn restore_default(&mut self) {
+        self.value = self.default_value.clone();
+        self.notify_subscribers();
+    }
+
+    fn notify_subscribers(&mut self) {
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber
+                .borrow_mut()
+                .object_updated(self.index, self.sub_index, &self.value);
         }
     }
 
-    pub fn read(&self) -> &ObjectValue {
-        &self.value
+    pub fn read(&self) -> Result<ObjectValue, ObjectDictionaryError> {
+        match self.provider.as_ref() {
+            Some(provider) => {
+                let value = provider
+                    .borrow_mut()
+                    .provide_value(self.index, self.sub_index);
+                if !self.value.is_same_type(&value) {
+                    return Err(ObjectDictionaryError::DataTypeMismatch);
+                }
+                Ok(value)
+            }
+            None => Ok(self.value.clone()),
+        }
     }
 
     pub fn subscribe(&mut self, subscriber: Rc<RefCell<dyn ObjectSubscriber>>) {
@@ -55,41 +681,273 @@ impl Object {
     }
 }
 
+struct Object {
+    object_type: ObjectType,
+    name: Option<String>,
+    entries: BTreeMap<u8, Entry>,
+}
+
+impl Object {
+    fn new(object_type: ObjectType) -> Object {
+        Object {
+            object_type,
+            name: None,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    fn is_structured(&self) -> bool {
+        matches!(self.object_type, ObjectType::Array | ObjectType::Record)
+    }
+
+    fn highest_sub_index(&self) -> u8 {
+        self.entries.keys().next_back().cloned().unwrap_or(0)
+    }
+
+    /// Keeps sub-index 0 of arrays and records at the highest sub-index
+    /// supported. A writable sub-index 0, as in PDO mappings, is left to the
+    /// application.
+    fn update_sub_index_count(&mut self, index: u16) {
+        let highest = self.highest_sub_index();
+        if !self.is_structured() || highest == 0 {
+            return;
+        }
+        match self.entries.get_mut(&0) {
+            Some(entry) if entry.attributes.access_type.is_writable() => {}
+            Some(entry) => {
+                if let ObjectValue::Unsigned8(_) = entry.value {
+                    entry.value = ObjectValue::Unsigned8(highest);
+                    entry.default_value = ObjectValue::Unsigned8(highest);
+                }
+            }
+            None => {
+                self.entries.insert(
+                    0,
+                    Entry::new(
+                        index,
+                        0,
+                        ObjectValue::Unsigned8(highest),
+                        ObjectAttributes {
+                            access_type: AccessType::Const,
+                            name: Some(String::from("Highest sub-index supported")),
+                            ..Default::default()
+                        },
+                    ),
+                );
+            }
+        }
+    }
+
+    /// A writable sub-index 0 can't claim more sub-indices than exist.
+    fn check_sub_index_count(
+        &self,
+        sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        let writable = self
+            .entries
+            .get(&0)
+            .is_some_and(|entry| entry.attributes.access_type.is_writable());
+        match value {
+            ObjectValue::Unsigned8(count)
+                if sub_index == 0
+                    && writable
+                    && self.is_structured()
+                    && *count > self.highest_sub_index() =>
+            {
+                Err(ObjectDictionaryError::ValueTooHigh)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+type RangeSubscriber = (RangeInclusive<u16>, Rc<RefCell<dyn ObjectSubscriber>>);
+type RangeValidator = (RangeInclusive<u16>, Rc<RefCell<dyn ObjectValidator>>);
+
 #[derive(Default)]
 pub struct ObjectDictionary {
-    dict: HashMap<ObjectKey, Object>,
+    objects: HashMap<u16, Object>,
+    range_subscribers: Vec<RangeSubscriber>,
+    range_validators: Vec<RangeValidator>,
 }
 
 impl ObjectDictionary {
     pub fn new() -> ObjectDictionary {
         ObjectDictionary {
-            dict: HashMap::new(),
+            objects: HashMap::new(),
+            range_subscribers: Vec::new(),
+            range_validators: Vec::new(),
         }
     }
 
+    /// Sets the structure and name of an object, creating it without entries
+    /// if needed. Objects created through `add` are VAR (or DOMAIN) objects
+    /// when only sub-index 0 is added and RECORD objects otherwise.
+    pub fn add_object(&mut self, index: u16, object_type: ObjectType, name: Option<String>) {
+        let object = self
+            .objects
+            .entry(index)
+            .or_insert_with(|| Object::new(object_type));
+        object.object_type = object_type;
+        object.name = name;
+        object.update_sub_index_count(index);
+    }
+
     pub fn add(&mut self, index: u16, sub_index: u8, value: ObjectValue) {
-        self.dict.insert(
-            ObjectKey { index, sub_index },
-            Object {
-                index,
-                sub_index,
-                value,
-                subscribers: Vec::new(),
-            },
-        );
+        self.add_with_attributes(index, sub_index, value, ObjectAttributes::default());
     }
 
-    pub fn write(&mut self, index: u16, sub_index: u8, value: ObjectValue) {
-        if let Some(obj) = self.dict.get_mut(&ObjectKey { index, sub_index }) {
-            obj.write(value);
+    pub fn add_with_attributes(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+        attributes: ObjectAttributes,
+    ) {
+        let object = self.objects.entry(index).or_insert_with(|| {
+            Object::new(match (sub_index, &value) {
+                (0, ObjectValue::Domain(_)) => ObjectType::Domain,
+                (0, _) => ObjectType::Var,
+                _ => ObjectType::Record,
+            })
+        });
+        if sub_index != 0 && !object.is_structured() {
+            object.object_type = ObjectType::Record;
+        }
+        let mut entry = Entry::new(index, sub_index, value, attributes);
+        for (indices, subscriber) in self.range_subscribers.iter() {
+            if indices.contains(&index) {
+                entry.subscribe(subscriber.clone());
+            }
+        }
+        for (indices, validator) in self.range_validators.iter() {
+            if indices.contains(&index) {
+                entry.validators.push(validator.clone());
+            }
         }
+        object.entries.insert(sub_index, entry);
+        object.update_sub_index_count(index);
+    }
+
+    pub fn object_type(&self, index: u16) -> Result<ObjectType, ObjectDictionaryError> {
+        Ok(self.get_object(index)?.object_type)
+    }
+
+    pub fn object_name(&self, index: u16) -> Result<Option<&str>, ObjectDictionaryError> {
+        Ok(self.get_object(index)?.name.as_deref())
+    }
+
+    /// All indices in the dictionary in ascending order.
+    pub fn indices(&self) -> Vec<u16> {
+        let mut indices: Vec<u16> = self.objects.keys().cloned().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// The sub-indices of an object in ascending order.
+    pub fn sub_indices(&self, index: u16) -> Result<Vec<u8>, ObjectDictionaryError> {
+        Ok(self.get_object(index)?.entries.keys().cloned().collect())
+    }
+
+    pub fn attributes(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<&ObjectAttributes, ObjectDictionaryError> {
+        Ok(&self.get(index, sub_index)?.attributes)
+    }
+
+    pub fn write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.get(index, sub_index)?;
+        self.get_object(index)?
+            .check_sub_index_count(sub_index, &value)?;
+        self.get_mut(index, sub_index)?.write(value)
+    }
+
+    /// The current value of an entry, taken from its provider if one is set.
+    pub fn read(&self, index: u16, sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        self.get(index, sub_index)?.read()
     }
 
-    pub fn read(&self, index: u16, sub_index: u8) -> Option<&ObjectValue> {
-        match self.dict.get(&ObjectKey { index, sub_index }) {
-            Some(obj) => Some(obj.read()),
-            None => None,
+    /// The data type of an entry, without consulting its provider.
+    pub fn data_type(&self, index: u16, sub_index: u8) -> Result<DataType, ObjectDictionaryError> {
+        Ok(self.get(index, sub_index)?.value.data_type())
+    }
+
+    pub fn default_value(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<&ObjectValue, ObjectDictionaryError> {
+        Ok(&self.get(index, sub_index)?.default_value)
+    }
+
+    /// Resets every entry in `area` to its default value, subscribers are
+    /// notified as for a regular write.
+    pub fn restore_defaults(&mut self, area: ParameterArea) {
+        for (index, object) in self.objects.iter_mut() {
+            if area.contains(*index) {
+                for entry in object.entries.values_mut() {
+                    entry.restore_default();
+                }
+            }
+        }
+    }
+
+    /// Writes on behalf of a remote node, enforcing the access type of the
+    /// entry and its validators. Local writes through `write` are not
+    /// restricted. For an entry with a command the command is executed
+    /// instead of storing the value.
+    pub fn remote_write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        let entry = self.get(index, sub_index)?;
+        if !entry.attributes.access_type.is_writable() {
+            return Err(ObjectDictionaryError::ReadOnly);
+        }
+        self.get_object(index)?
+            .check_sub_index_count(sub_index, &value)?;
+        entry.validate(self, &value)?;
+        if let Some(command) = entry.command.clone() {
+            return command.borrow_mut().execute(self, index, sub_index, &value);
+        }
+        self.write(index, sub_index, value)
+    }
+
+    /// Reads on behalf of a remote node, enforcing the access type of the
+    /// entry.
+    pub fn remote_read(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<ObjectValue, ObjectDictionaryError> {
+        let entry = self.get(index, sub_index)?;
+        if !entry.attributes.access_type.is_readable() {
+            return Err(ObjectDictionaryError::WriteOnly);
         }
+        entry.read()
+    }
+
+    /// All (index, sub-index) pairs in the dictionary in ascending order.
+    pub fn entries(&self) -> Vec<(u16, u8)> {
+        self.indices()
+            .into_iter()
+            .flat_map(|index| {
+                self.objects[&index]
+                    .entries
+                    .keys()
+                    .map(move |sub_index| (index, *sub_index))
+            })
+            .collect()
     }
 
     pub fn subscribe(
@@ -97,9 +955,122 @@ impl ObjectDictionary {
         index: u16,
         sub_index: u8,
         subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.get_mut(index, sub_index)?.subscribe(subscriber);
+        Ok(())
+    }
+
+    /// Subscribes to every entry with an index in `indices`, including the
+    /// entries added later.
+    pub fn subscribe_range(
+        &mut self,
+        indices: RangeInclusive<u16>,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) {
+        for (index, object) in self.objects.iter_mut() {
+            if indices.contains(index) {
+                for entry in object.entries.values_mut() {
+                    entry.subscribe(subscriber.clone());
+                }
+            }
+        }
+        self.range_subscribers.push((indices, subscriber));
+    }
+
+    /// Adds a check for remote writes to every entry with an index in
+    /// `indices`, including the entries added later.
+    pub fn add_range_validator(
+        &mut self,
+        indices: RangeInclusive<u16>,
+        validator: Rc<RefCell<dyn ObjectValidator>>,
     ) {
-        if let Some(obj) = self.dict.get_mut(&ObjectKey { index, sub_index }) {
-            obj.subscribe(subscriber);
+        for (index, object) in self.objects.iter_mut() {
+            if indices.contains(index) {
+                for entry in object.entries.values_mut() {
+                    entry.validators.push(validator.clone());
+                }
+            }
         }
+        self.range_validators.push((indices, validator));
+    }
+
+    /// Adds a check that remote writes to the entry have to pass.
+    pub fn add_validator(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        validator: Rc<RefCell<dyn ObjectValidator>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.get_mut(index, sub_index)?.validators.push(validator);
+        Ok(())
+    }
+
+    /// Makes remote writes to the entry execute `command`.
+    pub fn set_command(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        command: Rc<RefCell<dyn ObjectCommand>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.get_mut(index, sub_index)?.command = Some(command);
+        Ok(())
+    }
+
+    /// Makes every read of the entry, local or remote, return the value
+    /// supplied by `provider` instead of the stored one.
+    pub fn set_provider(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        provider: Rc<RefCell<dyn ObjectProvider>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.get_mut(index, sub_index)?.provider = Some(provider);
+        Ok(())
+    }
+
+    fn get_object(&self, index: u16) -> Result<&Object, ObjectDictionaryError> {
+        self.objects
+            .get(&index)
+            .ok_or(ObjectDictionaryError::ObjectDoesNotExist)
+    }
+
+    fn get(&self, index: u16, sub_index: u8) -> Result<&Entry, ObjectDictionaryError> {
+        self.get_object(index)?
+            .entries
+            .get(&sub_index)
+            .ok_or(ObjectDictionaryError::SubIndexDoesNotExist)
+    }
+
+    fn get_mut(&mut self, index: u16, sub_index: u8) -> Result<&mut Entry, ObjectDictionaryError> {
+        self.objects
+            .get_mut(&index)
+            .ok_or(ObjectDictionaryError::ObjectDoesNotExist)?
+            .entries
+            .get_mut(&sub_index)
+            .ok_or(ObjectDictionaryError::SubIndexDoesNotExist)
+    }
+}
+
+impl ObjectAccess for ObjectDictionary {
+    fn read(&self, index: u16, sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        ObjectDictionary::read(self, index, sub_index)
+    }
+
+    fn write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        ObjectDictionary::write(self, index, sub_index, value)
+    }
+
+    fn subscribe(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        ObjectDictionary::subscribe(self, index, sub_index, subscriber)
     }
 }
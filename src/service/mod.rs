@@ -1 +1,7 @@
 pub mod node_control;
+pub mod pdo;
+pub mod rpdo;
+pub mod sdo;
+pub mod sdo_client;
+pub mod sdo_server;
+pub mod tpdo;
@@ -0,0 +1,705 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::od::{
+    ObjectDictionary, ObjectDictionaryError, ObjectSubscriber, ObjectValidator, ObjectValue,
+};
+
+pub const RPDO_COMMUNICATION: u16 = 0x1400;
+pub const TPDO_COMMUNICATION: u16 = 0x1800;
+
+/// Distance between a communication parameter object and its mapping
+/// parameter object, and the number of PDOs each range can describe.
+pub const PDO_COUNT: u16 = 0x200;
+
+/// Set in a COB-ID when the PDO does not exist or is disabled.
+pub const COB_ID_INVALID: u32 = 1 << 31;
+/// Set in a COB-ID for a 29-bit CAN ID, only 11-bit IDs are supported.
+const COB_ID_EXTENDED: u32 = 1 << 29;
+/// Set in a COB-ID when the PDO can't be requested with a remote frame.
+pub const COB_ID_NO_RTR: u32 = 1 << 30;
+const COB_ID_CAN_ID: u32 = 0x7FF;
+
+/// Most data a PDO can carry, in bits.
+const MAX_PDO_BITS: usize = 64;
+
+/// Transmission types up to this one are synchronous.
+pub const TRANSMISSION_SYNCHRONOUS_MAX: u8 = 240;
+/// TPDO transmitted on RTR only, with the data sampled at the last SYNC.
+/// Type 253 is transmitted on RTR only with the current data.
+pub const TRANSMISSION_RTR_SYNCHRONOUS: u8 = 252;
+/// Transmission types from this one on are event-driven.
+pub const TRANSMISSION_EVENT_DRIVEN: u8 = 254;
+
+/// Communication parameters of a PDO, from 0x1400 - 0x15FF for RPDOs and
+/// 0x1800 - 0x19FF for TPDOs. The inhibit time (sub-index 3) is in 100 µs,
+/// the event timer (sub-index 5) in ms, both read as 0 when the object
+/// doesn't have them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Communication {
+    pub cob_id: u32,
+    pub transmission_type: u8,
+    pub inhibit_time: u16,
+    pub event_timer: u16,
+}
+
+impl Communication {
+    pub fn read(od: &ObjectDictionary, index: u16) -> Option<Communication> {
+        match (od.read(index, 0x01), od.read(index, 0x02)) {
+            (
+                Ok(ObjectValue::Unsigned32(cob_id)),
+                Ok(ObjectValue::Unsigned8(transmission_type)),
+            ) => Some(Communication {
+                cob_id,
+                transmission_type,
+                inhibit_time: match od.read(index, 0x03) {
+                    Ok(ObjectValue::Unsigned16(inhibit_time)) => inhibit_time,
+                    _ => 0,
+                },
+                event_timer: match od.read(index, 0x05) {
+                    Ok(ObjectValue::Unsigned16(event_timer)) => event_timer,
+                    _ => 0,
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.cob_id & COB_ID_INVALID == 0
+    }
+
+    pub fn can_id(&self) -> u16 {
+        (self.cob_id & COB_ID_CAN_ID) as u16
+    }
+
+    pub fn is_rtr_allowed(&self) -> bool {
+        self.cob_id & COB_ID_NO_RTR == 0
+    }
+
+    pub fn is_synchronous(&self) -> bool {
+        self.transmission_type <= TRANSMISSION_SYNCHRONOUS_MAX
+    }
+
+    pub fn is_event_driven(&self) -> bool {
+        self.transmission_type >= TRANSMISSION_EVENT_DRIVEN
+    }
+
+    /// Minimum time between two transmissions of an event-driven TPDO.
+    pub fn inhibit_duration(&self) -> Duration {
+        Duration::from_micros(self.inhibit_time as u64 * 100)
+    }
+}
+
+/// Set when a communication or mapping parameter was written.
+struct ParameterWrites {
+    written: bool,
+}
+
+impl ObjectSubscriber for ParameterWrites {
+    fn object_updated(&mut self, _index: u16, _sub_index: u8, _value: &ObjectValue) {
+        self.written = true;
+    }
+}
+
+/// The valid PDOs of one direction by communication parameter index. The
+/// list is only rebuilt after one of their parameters was written, so frames
+/// don't cost a scan of the whole parameter range.
+pub struct ValidPdos {
+    communication_index: u16,
+    writes: Rc<RefCell<ParameterWrites>>,
+    pdos: Vec<(u16, Communication)>,
+}
+
+impl ValidPdos {
+    /// Covers the PDOs whose communication parameters start at
+    /// `communication_index`, `RPDO_COMMUNICATION` or `TPDO_COMMUNICATION`.
+    pub fn new(communication_index: u16) -> ValidPdos {
+        ValidPdos {
+            communication_index,
+            writes: Rc::new(RefCell::new(ParameterWrites { written: true })),
+            pdos: Vec::new(),
+        }
+    }
+
+    /// Watches the communication and mapping parameters for writes, including
+    /// those of objects added later.
+    pub fn subscribe(&self, od: &mut ObjectDictionary) {
+        let first = self.communication_index;
+        od.subscribe_range(first..=first + 2 * PDO_COUNT - 1, self.writes.clone());
+    }
+
+    /// Rebuilds the list on next use, for parameters that changed without a
+    /// write such as newly added objects.
+    pub fn invalidate(&mut self) {
+        self.writes.borrow_mut().written = true;
+    }
+
+    pub fn get(&mut self, od: &ObjectDictionary) -> &[(u16, Communication)] {
+        if std::mem::take(&mut self.writes.borrow_mut().written) {
+            let first = self.communication_index;
+            self.pdos = (first..first + PDO_COUNT)
+                .filter_map(|index| match Communication::read(od, index) {
+                    Some(communication) if communication.is_valid() => Some((index, communication)),
+                    _ => None,
+                })
+                .collect();
+        }
+        &self.pdos
+    }
+}
+
+/// One entry of a mapping parameter object, encoded as index (bits 16 - 31),
+/// sub-index (bits 8 - 15) and length in bits (bits 0 - 7).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MappedObject {
+    pub index: u16,
+    pub sub_index: u8,
+    pub bit_length: u8,
+}
+
+impl MappedObject {
+    pub fn from_u32(value: u32) -> MappedObject {
+        MappedObject {
+            index: (value >> 16) as u16,
+            sub_index: (value >> 8) as u8,
+            bit_length: value as u8,
+        }
+    }
+
+    /// Dummy entries reserve space in the PDO for the static data types, they
+    /// don't refer to an object.
+    pub fn is_dummy(&self) -> bool {
+        (0x0001..=0x0007).contains(&self.index)
+    }
+}
+
+/// The objects mapped by the mapping parameter object at `index`, as many as
+/// its sub-index 0 tells.
+pub fn mapping(
+    od: &ObjectDictionary,
+    index: u16,
+) -> Result<Vec<MappedObject>, ObjectDictionaryError> {
+    let count = match od.read(index, 0x00)? {
+        ObjectValue::Unsigned8(count) => count,
+        _ => return Err(ObjectDictionaryError::DataTypeMismatch),
+    };
+    (1..=count)
+        .map(|sub_index| match od.read(index, sub_index)? {
+            ObjectValue::Unsigned32(value) => Ok(MappedObject::from_u32(value)),
+            _ => Err(ObjectDictionaryError::DataTypeMismatch),
+        })
+        .collect()
+}
+
+/// Writes the received PDO data into the mapped objects, subscribers are
+/// notified as for any local write. Returns false without writing anything
+/// when the data is shorter than the mapping.
+pub fn unpack(od: &mut ObjectDictionary, mapping: &[MappedObject], data: &[u8]) -> bool {
+    let total_bits: usize = mapping.iter().map(|m| m.bit_length as usize).sum();
+    if total_bits > data.len() * 8 {
+        return false;
+    }
+
+    let mut offset = 0;
+    for mapped in mapping.iter() {
+        let mut bytes = extract_bits(data, offset, mapped.bit_length as usize);
+        offset += mapped.bit_length as usize;
+        if mapped.is_dummy() {
+            continue;
+        }
+        let data_type = match od.data_type(mapped.index, mapped.sub_index) {
+            Ok(data_type) => data_type,
+            Err(_) => continue,
+        };
+        if let Some(size) = data_type.size() {
+            bytes.resize(size, 0);
+        }
+        if let Ok(value) = ObjectValue::from_bytes(data_type, &bytes) {
+            let _ = od.write(mapped.index, mapped.sub_index, value);
+        }
+    }
+    true
+}
+
+/// Enforces the CiA 301 procedure for changing PDO parameters at runtime:
+/// the COB-ID can't move while the PDO is valid, and the mapping can only be
+/// changed while the PDO is invalid and its sub-index 0 is 0. Writing the
+/// number of entries checks the whole mapping.
+pub struct PdoValidator;
+
+impl ObjectValidator for PdoValidator {
+    fn validate_write(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        let (communication_index, receive, is_mapping) = match index {
+            0x1400..=0x15FF => (index, true, false),
+            0x1600..=0x17FF => (index - PDO_COUNT, true, true),
+            0x1800..=0x19FF => (index, false, false),
+            0x1A00..=0x1BFF => (index - PDO_COUNT, false, true),
+            _ => return Ok(()),
+        };
+        let communication = Communication::read(od, communication_index);
+        let pdo_valid = communication.is_some_and(|c| c.is_valid());
+
+        if !is_mapping {
+            return match (communication, value) {
+                (_, ObjectValue::Unsigned32(cob_id))
+                    if sub_index == 0x01 && (cob_id & COB_ID_EXTENDED) != 0 =>
+                {
+                    Err(ObjectDictionaryError::ValueRangeExceeded)
+                }
+                (Some(current), ObjectValue::Unsigned32(cob_id)) if sub_index == 0x01 => {
+                    let changed = Communication {
+                        cob_id: *cob_id,
+                        ..current
+                    };
+                    if current.is_valid()
+                        && changed.is_valid()
+                        && changed.can_id() != current.can_id()
+                    {
+                        Err(ObjectDictionaryError::ValueRangeExceeded)
+                    } else {
+                        Ok(())
+                    }
+                }
+                // The inhibit time of a valid TPDO can't be changed.
+                (Some(_), _) if sub_index == 0x03 && !receive && pdo_valid => {
+                    Err(ObjectDictionaryError::ValueRangeExceeded)
+                }
+                _ => Ok(()),
+            };
+        }
+
+        if pdo_valid {
+            return Err(ObjectDictionaryError::DeviceState);
+        }
+        match value {
+            ObjectValue::Unsigned8(count) if sub_index == 0 => {
+                let mut total_bits = 0;
+                for sub_index in 1..=*count {
+                    let mapped = match od.read(index, sub_index)? {
+                        ObjectValue::Unsigned32(value) => MappedObject::from_u32(value),
+                        _ => return Err(ObjectDictionaryError::DataTypeMismatch),
+                    };
+                    check_mappable(od, &mapped, receive)?;
+                    total_bits += mapped.bit_length as usize;
+                }
+                if total_bits > MAX_PDO_BITS {
+                    return Err(ObjectDictionaryError::PdoLengthExceeded);
+                }
+                Ok(())
+            }
+            ObjectValue::Unsigned32(value) => {
+                if od.read(index, 0x00) != Ok(ObjectValue::Unsigned8(0)) {
+                    return Err(ObjectDictionaryError::DeviceState);
+                }
+                // Unused entries may be cleared.
+                if *value == 0 {
+                    return Ok(());
+                }
+                let mapped = MappedObject::from_u32(*value);
+                check_mappable(od, &mapped, receive)?;
+                if mapped.bit_length as usize > MAX_PDO_BITS {
+                    return Err(ObjectDictionaryError::PdoLengthExceeded);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A mapped object has to exist, be PDO mappable and accessible in the
+/// direction of the PDO, and fit in the mapped length. Dummy entries are
+/// only allowed in RPDOs.
+fn check_mappable(
+    od: &ObjectDictionary,
+    mapped: &MappedObject,
+    receive: bool,
+) -> Result<(), ObjectDictionaryError> {
+    if mapped.bit_length == 0 {
+        return Err(ObjectDictionaryError::NotMappable);
+    }
+    if mapped.is_dummy() {
+        return if receive {
+            Ok(())
+        } else {
+            Err(ObjectDictionaryError::NotMappable)
+        };
+    }
+
+    let attributes = od.attributes(mapped.index, mapped.sub_index)?;
+    let accessible = if receive {
+        attributes.access_type.is_writable()
+    } else {
+        attributes.access_type.is_readable()
+    };
+    if !attributes.pdo_mappable || !accessible {
+        return Err(ObjectDictionaryError::NotMappable);
+    }
+    match od.data_type(mapped.index, mapped.sub_index)?.size() {
+        Some(size) if mapped.bit_length as usize > size * 8 => {
+            Err(ObjectDictionaryError::NotMappable)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Builds the data of a PDO from the current values of the mapped objects,
+/// dummy entries are sent as zeros.
+pub fn pack(
+    od: &ObjectDictionary,
+    mapping: &[MappedObject],
+) -> Result<Vec<u8>, ObjectDictionaryError> {
+    let total_bits: usize = mapping.iter().map(|m| m.bit_length as usize).sum();
+    let mut data = vec![0; total_bits.div_ceil(8)];
+
+    let mut offset = 0;
+    for mapped in mapping.iter() {
+        if !mapped.is_dummy() {
+            let bytes = od.read(mapped.index, mapped.sub_index)?.to_bytes();
+            insert_bits(&mut data, offset, &bytes, mapped.bit_length as usize);
+        }
+        offset += mapped.bit_length as usize;
+    }
+    Ok(data)
+}
+
+/// Copies `length` bits starting at bit `offset` of `data`, counted from the
+/// least significant bit of the first byte.
+fn extract_bits(data: &[u8], offset: usize, length: usize) -> Vec<u8> {
+    let mut bytes = vec![0; length.div_ceil(8)];
+    for bit in 0..length {
+        let source = offset + bit;
+        if data[source / 8] & (1 << (source % 8)) != 0 {
+            bytes[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+    bytes
+}
+
+/// Copies the low `length` bits of `bytes` into `data` at bit `offset`, bits
+/// past the end of `bytes` are left at zero.
+fn insert_bits(data: &mut [u8], offset: usize, bytes: &[u8], length: usize) {
+    for bit in 0..length.min(bytes.len() * 8) {
+        if bytes[bit / 8] & (1 << (bit % 8)) != 0 {
+            let target = offset + bit;
+            data[target / 8] |= 1 << (target % 8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::od::{
+        AccessType, ObjectAttributes, ObjectDictionary, ObjectDictionaryError, ObjectValue,
+    };
+    use crate::service::pdo::*;
+
+    fn create_od() -> ObjectDictionary {
+        let mut od = ObjectDictionary::new();
+        od.add(0x2000, 0x00, ObjectValue::Unsigned8(0));
+        od.add(0x2001, 0x00, ObjectValue::Integer16(0));
+        od.add(0x2002, 0x00, ObjectValue::Boolean(false));
+        od.add(0x1600, 0x00, ObjectValue::Unsigned8(0));
+        for sub_index in 1..=8 {
+            od.add(0x1600, sub_index, ObjectValue::Unsigned32(0));
+        }
+        od
+    }
+
+    #[test]
+    fn test_mapped_object_encoding() {
+        let mapped = MappedObject::from_u32(0x2001_0210);
+        assert_eq!(
+            mapped,
+            MappedObject {
+                index: 0x2001,
+                sub_index: 0x02,
+                bit_length: 0x10
+            }
+        );
+        assert!(!mapped.is_dummy());
+        assert!(MappedObject::from_u32(0x0005_0008).is_dummy());
+    }
+
+    #[test]
+    fn test_communication() {
+        let mut od = ObjectDictionary::new();
+        assert_eq!(Communication::read(&od, 0x1400), None);
+        od.add(0x1400, 0x01, ObjectValue::Unsigned32(0x8000_0201));
+        od.add(0x1400, 0x02, ObjectValue::Unsigned8(0x01));
+
+        let communication = Communication::read(&od, 0x1400).unwrap();
+        assert!(!communication.is_valid());
+        assert!(communication.is_synchronous());
+        assert!(!communication.is_event_driven());
+        assert!(communication.is_rtr_allowed());
+        assert_eq!(communication.can_id(), 0x201);
+        assert_eq!(communication.inhibit_time, 0);
+        assert_eq!(communication.event_timer, 0);
+
+        od.add(0x1800, 0x01, ObjectValue::Unsigned32(0x4000_0181));
+        od.add(0x1800, 0x02, ObjectValue::Unsigned8(0xFE));
+        od.add(0x1800, 0x03, ObjectValue::Unsigned16(25));
+        od.add(0x1800, 0x05, ObjectValue::Unsigned16(100));
+        let communication = Communication::read(&od, 0x1800).unwrap();
+        assert!(communication.is_valid());
+        assert!(!communication.is_rtr_allowed());
+        assert_eq!(communication.can_id(), 0x181);
+        assert!(communication.is_event_driven());
+        assert_eq!(
+            communication.inhibit_duration(),
+            Duration::from_micros(2500)
+        );
+        assert_eq!(communication.event_timer, 100);
+    }
+
+    #[test]
+    fn test_valid_pdos() {
+        let mut od = ObjectDictionary::new();
+        od.add(0x1400, 0x01, ObjectValue::Unsigned32(0x201));
+        od.add(0x1400, 0x02, ObjectValue::Unsigned8(0xFF));
+        od.add(0x1401, 0x01, ObjectValue::Unsigned32(0x8000_0301));
+        od.add(0x1401, 0x02, ObjectValue::Unsigned8(0xFF));
+        let mut valid = ValidPdos::new(RPDO_COMMUNICATION);
+        valid.subscribe(&mut od);
+
+        let pdos: Vec<u16> = valid.get(&od).iter().map(|(index, _)| *index).collect();
+        assert_eq!(pdos, vec![0x1400]);
+
+        od.write(0x1401, 0x01, ObjectValue::Unsigned32(0x301))
+            .unwrap();
+        let pdos: Vec<u16> = valid.get(&od).iter().map(|(index, _)| *index).collect();
+        assert_eq!(pdos, vec![0x1400, 0x1401]);
+
+        // Objects added afterwards are seen once written or invalidated.
+        od.add(0x1402, 0x01, ObjectValue::Unsigned32(0x8000_0401));
+        od.add(0x1402, 0x02, ObjectValue::Unsigned8(0xFF));
+        assert_eq!(valid.get(&od).len(), 2);
+        od.write(0x1402, 0x01, ObjectValue::Unsigned32(0x401))
+            .unwrap();
+        assert_eq!(valid.get(&od).len(), 3);
+        od.add(0x1403, 0x01, ObjectValue::Unsigned32(0x501));
+        od.add(0x1403, 0x02, ObjectValue::Unsigned8(0xFF));
+        valid.invalidate();
+        assert_eq!(valid.get(&od).len(), 4);
+    }
+
+    #[test]
+    fn test_mapping() {
+        let mut od = create_od();
+        assert_eq!(mapping(&od, 0x1600), Ok(vec![]));
+        od.write(0x1600, 0x01, ObjectValue::Unsigned32(0x2000_0008))
+            .unwrap();
+        od.write(0x1600, 0x02, ObjectValue::Unsigned32(0x2001_0010))
+            .unwrap();
+        od.write(0x1600, 0x00, ObjectValue::Unsigned8(1)).unwrap();
+        assert_eq!(
+            mapping(&od, 0x1600),
+            Ok(vec![MappedObject::from_u32(0x2000_0008)])
+        );
+    }
+
+    #[test]
+    fn test_unpack() {
+        let mut od = create_od();
+        let mapping = [
+            MappedObject::from_u32(0x2002_0001),
+            MappedObject::from_u32(0x0005_0007),
+            MappedObject::from_u32(0x2001_0010),
+            MappedObject::from_u32(0x2000_0008),
+        ];
+
+        assert!(!unpack(&mut od, &mapping, &[0x01, 0xFE, 0xFF]));
+        assert_eq!(od.read(0x2002, 0x00), Ok(ObjectValue::Boolean(false)));
+
+        assert!(unpack(&mut od, &mapping, &[0x01, 0xFE, 0xFF, 0x42]));
+        assert_eq!(od.read(0x2002, 0x00), Ok(ObjectValue::Boolean(true)));
+        assert_eq!(od.read(0x2001, 0x00), Ok(ObjectValue::Integer16(-2)));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(0x42)));
+    }
+
+    #[test]
+    fn test_pack() {
+        let mut od = create_od();
+        od.write(0x2000, 0x00, ObjectValue::Unsigned8(0x42))
+            .unwrap();
+        od.write(0x2001, 0x00, ObjectValue::Integer16(-2)).unwrap();
+        od.write(0x2002, 0x00, ObjectValue::Boolean(true)).unwrap();
+        let mapping = [
+            MappedObject::from_u32(0x2002_0001),
+            MappedObject::from_u32(0x0005_0007),
+            MappedObject::from_u32(0x2001_0010),
+            MappedObject::from_u32(0x2000_0008),
+        ];
+
+        assert_eq!(pack(&od, &mapping), Ok(vec![0x01, 0xFE, 0xFF, 0x42]));
+        assert_eq!(pack(&od, &[]), Ok(vec![]));
+        assert_eq!(
+            pack(&od, &[MappedObject::from_u32(0x3000_0008)]),
+            Err(ObjectDictionaryError::ObjectDoesNotExist)
+        );
+    }
+
+    fn create_tpdo_od() -> ObjectDictionary {
+        let mut od = ObjectDictionary::new();
+        let mappable = ObjectAttributes {
+            pdo_mappable: true,
+            ..Default::default()
+        };
+        od.add_with_attributes(0x2000, 0x00, ObjectValue::Unsigned32(0), mappable.clone());
+        od.add_with_attributes(0x2001, 0x00, ObjectValue::Unsigned64(0), mappable.clone());
+        od.add_with_attributes(
+            0x2002,
+            0x00,
+            ObjectValue::Unsigned8(0),
+            ObjectAttributes {
+                access_type: AccessType::WriteOnly,
+                ..mappable
+            },
+        );
+        od.add(0x2003, 0x00, ObjectValue::Unsigned8(0));
+        od.add(0x1800, 0x01, ObjectValue::Unsigned32(0x185));
+        od.add(0x1800, 0x02, ObjectValue::Unsigned8(0xFF));
+        od.add(0x1800, 0x03, ObjectValue::Unsigned16(0));
+        od.add(0x1A00, 0x00, ObjectValue::Unsigned8(1));
+        for sub_index in 1..=8 {
+            od.add(0x1A00, sub_index, ObjectValue::Unsigned32(0));
+        }
+        od.write(0x1A00, 0x01, ObjectValue::Unsigned32(0x2000_0020))
+            .unwrap();
+        for (index, sub_index) in od.entries() {
+            od.add_validator(index, sub_index, Rc::new(RefCell::new(PdoValidator)))
+                .unwrap();
+        }
+        od
+    }
+
+    #[test]
+    fn test_cob_id_change() {
+        let mut od = create_tpdo_od();
+        assert_eq!(
+            od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x186)),
+            Err(ObjectDictionaryError::ValueRangeExceeded)
+        );
+        assert_eq!(
+            od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x8000_0185)),
+            Ok(())
+        );
+        assert_eq!(
+            od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x186)),
+            Ok(())
+        );
+        assert_eq!(
+            od.remote_write(0x1800, 0x02, ObjectValue::Unsigned8(1)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_extended_cob_id() {
+        let mut od = create_tpdo_od();
+        assert_eq!(
+            od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0xA000_0185)),
+            Err(ObjectDictionaryError::ValueRangeExceeded)
+        );
+        assert_eq!(
+            od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x2000_0185)),
+            Err(ObjectDictionaryError::ValueRangeExceeded)
+        );
+        assert_eq!(od.read(0x1800, 0x01), Ok(ObjectValue::Unsigned32(0x185)));
+    }
+
+    #[test]
+    fn test_inhibit_time_change() {
+        let mut od = create_tpdo_od();
+        assert_eq!(
+            od.remote_write(0x1800, 0x03, ObjectValue::Unsigned16(10)),
+            Err(ObjectDictionaryError::ValueRangeExceeded)
+        );
+        od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x8000_0185))
+            .unwrap();
+        assert_eq!(
+            od.remote_write(0x1800, 0x03, ObjectValue::Unsigned16(10)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_remapping_procedure() {
+        let mut od = create_tpdo_od();
+        let write = |od: &mut ObjectDictionary, sub_index: u8, value: ObjectValue| {
+            od.remote_write(0x1A00, sub_index, value)
+        };
+
+        // The PDO has to be invalid and its mapping disabled first.
+        assert_eq!(
+            write(&mut od, 0x00, ObjectValue::Unsigned8(0)),
+            Err(ObjectDictionaryError::DeviceState)
+        );
+        od.remote_write(0x1800, 0x01, ObjectValue::Unsigned32(0x8000_0185))
+            .unwrap();
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x2001_0040)),
+            Err(ObjectDictionaryError::DeviceState)
+        );
+        assert_eq!(write(&mut od, 0x00, ObjectValue::Unsigned8(0)), Ok(()));
+
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x2003_0008)),
+            Err(ObjectDictionaryError::NotMappable)
+        );
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x2002_0008)),
+            Err(ObjectDictionaryError::NotMappable)
+        );
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x0005_0008)),
+            Err(ObjectDictionaryError::NotMappable)
+        );
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x2000_0040)),
+            Err(ObjectDictionaryError::NotMappable)
+        );
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x3000_0008)),
+            Err(ObjectDictionaryError::ObjectDoesNotExist)
+        );
+        assert_eq!(
+            write(&mut od, 0x02, ObjectValue::Unsigned32(0x2001_0040)),
+            Ok(())
+        );
+
+        // 32 + 64 bits don't fit in a PDO.
+        assert_eq!(
+            write(&mut od, 0x00, ObjectValue::Unsigned8(2)),
+            Err(ObjectDictionaryError::PdoLengthExceeded)
+        );
+        assert_eq!(write(&mut od, 0x01, ObjectValue::Unsigned32(0)), Ok(()));
+        assert_eq!(
+            write(&mut od, 0x00, ObjectValue::Unsigned8(2)),
+            Err(ObjectDictionaryError::NotMappable)
+        );
+        assert_eq!(
+            write(&mut od, 0x01, ObjectValue::Unsigned32(0x2001_0040)),
+            Ok(())
+        );
+        assert_eq!(write(&mut od, 0x00, ObjectValue::Unsigned8(1)), Ok(()));
+        assert_eq!(
+            mapping(&od, 0x1A00),
+            Ok(vec![MappedObject::from_u32(0x2001_0040)])
+        );
+    }
+}
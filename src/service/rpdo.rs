@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use crate::message::CanMessage;
+use crate::od::ObjectDictionary;
+use crate::service::pdo::*;
+
+/// Applies received PDOs to the object dictionary. Changes made to the
+/// configuration through SDO take effect with the next frame.
+pub struct ReceivePdos {
+    valid: ValidPdos,
+    /// Data of synchronous RPDOs waiting for the next SYNC, by communication
+    /// parameter index.
+    pending: BTreeMap<u16, Vec<u8>>,
+}
+
+impl Default for ReceivePdos {
+    fn default() -> Self {
+        ReceivePdos::new()
+    }
+}
+
+impl ReceivePdos {
+    pub fn new() -> ReceivePdos {
+        ReceivePdos {
+            valid: ValidPdos::new(RPDO_COMMUNICATION),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Watches the RPDO parameters for writes.
+    pub fn subscribe(&self, od: &mut ObjectDictionary) {
+        self.valid.subscribe(od);
+    }
+
+    pub fn reset(&mut self) {
+        self.valid.invalidate();
+        self.pending.clear();
+    }
+
+    /// Handles a frame if its CAN ID belongs to a valid RPDO. Data of
+    /// synchronous RPDOs is held until `sync`, the last frame received wins.
+    pub fn process(&mut self, od: &mut ObjectDictionary, can_message: &CanMessage) {
+        for &(index, communication) in self.valid.get(od).iter() {
+            if communication.can_id() != can_message.can_id() {
+                continue;
+            }
+            if communication.is_synchronous() {
+                self.pending.insert(index, can_message.data().clone());
+            } else {
+                apply(od, index, can_message.data());
+            }
+        }
+    }
+
+    /// Applies the data of synchronous RPDOs received since the last SYNC.
+    pub fn sync(&mut self, od: &mut ObjectDictionary) {
+        for (index, data) in std::mem::take(&mut self.pending) {
+            apply(od, index, &data);
+        }
+    }
+}
+
+fn apply(od: &mut ObjectDictionary, communication_index: u16, data: &[u8]) {
+    if let Ok(mapping) = mapping(od, communication_index + PDO_COUNT) {
+        unpack(od, &mapping, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::CanMessage;
+    use crate::od::{ObjectDictionary, ObjectValue};
+    use crate::service::rpdo::*;
+
+    fn create_od(transmission_type: u8) -> ObjectDictionary {
+        let mut od = ObjectDictionary::new();
+        od.add(0x2000, 0x00, ObjectValue::Unsigned16(0));
+        od.add(0x1400, 0x01, ObjectValue::Unsigned32(0x205));
+        od.add(0x1400, 0x02, ObjectValue::Unsigned8(transmission_type));
+        od.add(0x1600, 0x00, ObjectValue::Unsigned8(1));
+        od.add(0x1600, 0x01, ObjectValue::Unsigned32(0x2000_0010));
+        od
+    }
+
+    #[test]
+    fn test_asynchronous_rpdo() {
+        let mut od = create_od(0xFF);
+        let mut rpdos = ReceivePdos::new();
+
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x206, vec![0x34, 0x12]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(0)));
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x34, 0x12]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(0x1234)));
+    }
+
+    #[test]
+    fn test_synchronous_rpdo() {
+        let mut od = create_od(0x01);
+        let mut rpdos = ReceivePdos::new();
+
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x01, 0x00]));
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x02, 0x00]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(0)));
+        rpdos.sync(&mut od);
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(2)));
+
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x03, 0x00]));
+        rpdos.reset();
+        rpdos.sync(&mut od);
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(2)));
+    }
+
+    #[test]
+    fn test_cob_id_change() {
+        let mut od = create_od(0xFF);
+        let mut rpdos = ReceivePdos::new();
+        rpdos.subscribe(&mut od);
+
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x01, 0x00]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(1)));
+
+        od.write(0x1400, 0x01, ObjectValue::Unsigned32(0x206))
+            .unwrap();
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x02, 0x00]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(1)));
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x206, vec![0x03, 0x00]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(3)));
+    }
+
+    #[test]
+    fn test_invalid_rpdo() {
+        let mut od = create_od(0xFF);
+        od.write(0x1400, 0x01, ObjectValue::Unsigned32(0x8000_0205))
+            .unwrap();
+        let mut rpdos = ReceivePdos::new();
+
+        rpdos.process(&mut od, &CanMessage::from_can_id(0x205, vec![0x34, 0x12]));
+        assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned16(0)));
+    }
+}
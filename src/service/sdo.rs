@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use crate::od::{DataType, ObjectValue};
+
+pub const CCS_DOWNLOAD_SEGMENT: u8 = 0x0;
+pub const CCS_INITIATE_DOWNLOAD: u8 = 0x1;
+pub const CCS_INITIATE_UPLOAD: u8 = 0x2;
+pub const CCS_UPLOAD_SEGMENT: u8 = 0x3;
+pub const CCS_ABORT: u8 = 0x4;
+pub const CCS_BLOCK_UPLOAD: u8 = 0x5;
+pub const CCS_BLOCK_DOWNLOAD: u8 = 0x6;
+
+pub const ABORT_TOGGLE_BIT_NOT_ALTERNATED: u32 = 0x0503_0000;
+pub const ABORT_TIMEOUT: u32 = 0x0504_0000;
+pub const ABORT_COMMAND_SPECIFIER_INVALID: u32 = 0x0504_0001;
+pub const ABORT_LENGTH_MISMATCH: u32 = 0x0607_0010;
+
+pub const SEGMENT_SIZE: usize = 7;
+
+/// Time a peer may stay silent in the middle of a transfer before it is
+/// aborted with `ABORT_TIMEOUT`.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+pub fn abort_frame(index: u16, sub_index: u8, abort_code: u32) -> Vec<u8> {
+    initiate_frame(0x80, index, sub_index, &abort_code.to_le_bytes())
+}
+
+pub fn initiate_frame(command: u8, index: u16, sub_index: u8, payload: &[u8]) -> Vec<u8> {
+    let index_bytes = index.to_le_bytes();
+    let mut data = vec![
+        command,
+        index_bytes[0],
+        index_bytes[1],
+        sub_index,
+        0,
+        0,
+        0,
+        0,
+    ];
+    data[4..4 + payload.len()].copy_from_slice(payload);
+    data
+}
+
+pub fn segment_frame(command: u8, toggle: bool, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![command | ((toggle as u8) << 4), 0, 0, 0, 0, 0, 0, 0];
+    data[1..1 + payload.len()].copy_from_slice(payload);
+    data
+}
+
+/// Decodes received data into a value of `data_type`. When the size was not
+/// indicated by the peer, the data may contain padding that is dropped before
+/// decoding.
+pub fn decode_value(
+    data_type: DataType,
+    data: &[u8],
+    size_known: bool,
+) -> Result<ObjectValue, u32> {
+    let data = if size_known {
+        data
+    } else {
+        match data_type.size() {
+            Some(size) if size <= data.len() => &data[..size],
+            Some(_) => data,
+            None if data_type == DataType::Domain => data,
+            None => trim_padding(data),
+        }
+    };
+    ObjectValue::from_bytes(data_type, data).map_err(|e| e.abort_code())
+}
+
+fn trim_padding(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|b| *b != 0).map_or(0, |p| p + 1);
+    &data[..end]
+}
@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::cob::Cob;
+use crate::message::CanMessage;
+use crate::od::ObjectValue;
+use crate::service::sdo::*;
+
+const SCS_UPLOAD_SEGMENT: u8 = 0x0;
+const SCS_DOWNLOAD_SEGMENT: u8 = 0x1;
+const SCS_INITIATE_UPLOAD: u8 = 0x2;
+const SCS_INITIATE_DOWNLOAD: u8 = 0x3;
+const SCS_ABORT: u8 = 0x4;
+
+#[derive(Debug, PartialEq)]
+pub enum SdoOutcome {
+    Uploaded(Vec<u8>),
+    Downloaded,
+    Aborted(u32),
+    TimedOut,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SdoClientResult {
+    pub node_id: u8,
+    pub index: u16,
+    pub sub_index: u8,
+    pub outcome: SdoOutcome,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SdoClientError {
+    InvalidNodeId,
+    Busy,
+}
+
+enum State {
+    InitiateUpload,
+    UploadSegment { size: Option<usize> },
+    InitiateDownload { expedited: bool },
+    DownloadSegment { last_segment: bool },
+}
+
+struct Transaction {
+    index: u16,
+    sub_index: u8,
+    state: State,
+    toggle: bool,
+    data: Vec<u8>,
+    offset: usize,
+    elapsed: Duration,
+}
+
+pub struct SdoClient {
+    timeout: Duration,
+    transactions: HashMap<u8, Transaction>,
+    results: Vec<SdoClientResult>,
+}
+
+impl Default for SdoClient {
+    fn default() -> Self {
+        SdoClient::new()
+    }
+}
+
+impl SdoClient {
+    pub fn new() -> SdoClient {
+        SdoClient {
+            timeout: DEFAULT_TIMEOUT,
+            transactions: HashMap::new(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn upload(
+        &mut self,
+        node_id: u8,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<CanMessage, SdoClientError> {
+        self.check_available(node_id)?;
+
+        self.transactions.insert(
+            node_id,
+            Transaction::new(index, sub_index, State::InitiateUpload, Vec::new()),
+        );
+        Ok(request(
+            node_id,
+            initiate_frame(CCS_INITIATE_UPLOAD << 5, index, sub_index, &[]),
+        ))
+    }
+
+    pub fn download(
+        &mut self,
+        node_id: u8,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<CanMessage, SdoClientError> {
+        self.check_available(node_id)?;
+
+        let data = value.to_bytes();
+        let expedited = (1..=4).contains(&data.len());
+        let frame = if expedited {
+            let unused = (4 - data.len()) as u8;
+            initiate_frame(
+                (CCS_INITIATE_DOWNLOAD << 5) | (unused << 2) | 0x3,
+                index,
+                sub_index,
+                &data,
+            )
+        } else {
+            let size = (data.len() as u32).to_le_bytes();
+            initiate_frame((CCS_INITIATE_DOWNLOAD << 5) | 0x1, index, sub_index, &size)
+        };
+
+        self.transactions.insert(
+            node_id,
+            Transaction::new(
+                index,
+                sub_index,
+                State::InitiateDownload { expedited },
+                data,
+            ),
+        );
+        Ok(request(node_id, frame))
+    }
+
+    pub fn process(&mut self, can_message: CanMessage) -> Option<CanMessage> {
+        if can_message.cob() != Cob::SdoTx || can_message.data_length() != 8 {
+            return None;
+        }
+
+        let node_id = can_message.node_id();
+        let transaction = self.transactions.get_mut(&node_id)?;
+        transaction.elapsed = Duration::from_secs(0);
+
+        let data = can_message.data();
+        let step = match data[0] >> 5 {
+            SCS_ABORT => Step::Aborted(u32::from_le_bytes([data[4], data[5], data[6], data[7]])),
+            scs => transaction.step(scs, data),
+        };
+
+        match step {
+            Step::Continue(frame) => Some(request(node_id, frame)),
+            Step::Done(outcome) => {
+                self.finish(node_id, outcome);
+                None
+            }
+            Step::Aborted(abort_code) => {
+                self.finish(node_id, SdoOutcome::Aborted(abort_code));
+                None
+            }
+            Step::Abort(abort_code) => {
+                let transaction = self.finish(node_id, SdoOutcome::Aborted(abort_code));
+                Some(request(
+                    node_id,
+                    abort_frame(transaction.index, transaction.sub_index, abort_code),
+                ))
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: Duration) -> Vec<CanMessage> {
+        let timeout = self.timeout;
+        let mut timed_out = Vec::new();
+        for (node_id, transaction) in self.transactions.iter_mut() {
+            transaction.elapsed += dt;
+            if transaction.elapsed >= timeout {
+                timed_out.push(*node_id);
+            }
+        }
+
+        timed_out
+            .into_iter()
+            .map(|node_id| {
+                let transaction = self.finish(node_id, SdoOutcome::TimedOut);
+                request(
+                    node_id,
+                    abort_frame(transaction.index, transaction.sub_index, ABORT_TIMEOUT),
+                )
+            })
+            .collect()
+    }
+
+    pub fn fetch_results(&mut self) -> Vec<SdoClientResult> {
+        self.results.drain(..).collect()
+    }
+
+    fn check_available(&self, node_id: u8) -> Result<(), SdoClientError> {
+        if node_id == 0 || node_id > 0x7F {
+            Err(SdoClientError::InvalidNodeId)
+        } else if self.transactions.contains_key(&node_id) {
+            Err(SdoClientError::Busy)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn finish(&mut self, node_id: u8, outcome: SdoOutcome) -> Transaction {
+        let transaction = self.transactions.remove(&node_id).unwrap();
+        self.results.push(SdoClientResult {
+            node_id,
+            index: transaction.index,
+            sub_index: transaction.sub_index,
+            outcome,
+        });
+        transaction
+    }
+}
+
+enum Step {
+    Continue(Vec<u8>),
+    Done(SdoOutcome),
+    Aborted(u32),
+    Abort(u32),
+}
+
+impl Transaction {
+    fn new(index: u16, sub_index: u8, state: State, data: Vec<u8>) -> Transaction {
+        Transaction {
+            index,
+            sub_index,
+            state,
+            toggle: false,
+            data,
+            offset: 0,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    fn step(&mut self, scs: u8, data: &[u8]) -> Step {
+        match (&self.state, scs) {
+            (State::InitiateUpload, SCS_INITIATE_UPLOAD) => self.initiate_upload_response(data),
+            (State::UploadSegment { .. }, SCS_UPLOAD_SEGMENT) => self.upload_segment_response(data),
+            (State::InitiateDownload { .. }, SCS_INITIATE_DOWNLOAD) => {
+                self.initiate_download_response(data)
+            }
+            (State::DownloadSegment { .. }, SCS_DOWNLOAD_SEGMENT) => {
+                self.download_segment_response(data)
+            }
+            _ => Step::Abort(ABORT_COMMAND_SPECIFIER_INVALID),
+        }
+    }
+
+    fn is_same_object(&self, data: &[u8]) -> bool {
+        u16::from_le_bytes([data[1], data[2]]) == self.index && data[3] == self.sub_index
+    }
+
+    fn initiate_upload_response(&mut self, data: &[u8]) -> Step {
+        if !self.is_same_object(data) {
+            return Step::Abort(ABORT_COMMAND_SPECIFIER_INVALID);
+        }
+
+        let expedited = (data[0] & 0x2) != 0;
+        let size_indicated = (data[0] & 0x1) != 0;
+        if expedited {
+            let size = if size_indicated {
+                4 - ((data[0] >> 2) & 0x3) as usize
+            } else {
+                4
+            };
+            Step::Done(SdoOutcome::Uploaded(data[4..4 + size].to_vec()))
+        } else {
+            let size = if size_indicated {
+                Some(u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize)
+            } else {
+                None
+            };
+            self.state = State::UploadSegment { size };
+            Step::Continue(segment_frame(CCS_UPLOAD_SEGMENT << 5, self.toggle, &[]))
+        }
+    }
+
+    fn upload_segment_response(&mut self, data: &[u8]) -> Step {
+        let segment_toggle = (data[0] & 0x10) != 0;
+        if segment_toggle != self.toggle {
+            return Step::Abort(ABORT_TOGGLE_BIT_NOT_ALTERNATED);
+        }
+        self.toggle = !self.toggle;
+
+        let unused = ((data[0] >> 1) & 0x7) as usize;
+        self.data
+            .extend_from_slice(&data[1..1 + SEGMENT_SIZE - unused]);
+
+        let last_segment = (data[0] & 0x1) != 0;
+        if !last_segment {
+            return Step::Continue(segment_frame(CCS_UPLOAD_SEGMENT << 5, self.toggle, &[]));
+        }
+
+        match self.state {
+            State::UploadSegment { size: Some(size) } if size != self.data.len() => {
+                Step::Abort(ABORT_LENGTH_MISMATCH)
+            }
+            _ => Step::Done(SdoOutcome::Uploaded(std::mem::take(&mut self.data))),
+        }
+    }
+
+    fn initiate_download_response(&mut self, data: &[u8]) -> Step {
+        if !self.is_same_object(data) {
+            return Step::Abort(ABORT_COMMAND_SPECIFIER_INVALID);
+        }
+
+        match self.state {
+            State::InitiateDownload { expedited: true } => Step::Done(SdoOutcome::Downloaded),
+            _ => Step::Continue(self.next_download_segment()),
+        }
+    }
+
+    fn download_segment_response(&mut self, data: &[u8]) -> Step {
+        let segment_toggle = (data[0] & 0x10) != 0;
+        if segment_toggle != self.toggle {
+            return Step::Abort(ABORT_TOGGLE_BIT_NOT_ALTERNATED);
+        }
+        self.toggle = !self.toggle;
+
+        match self.state {
+            State::DownloadSegment { last_segment: true } => Step::Done(SdoOutcome::Downloaded),
+            _ => Step::Continue(self.next_download_segment()),
+        }
+    }
+
+    fn next_download_segment(&mut self) -> Vec<u8> {
+        let end = usize::min(self.offset + SEGMENT_SIZE, self.data.len());
+        let segment = &self.data[self.offset..end];
+        self.offset = end;
+
+        let last_segment = end == self.data.len();
+        self.state = State::DownloadSegment { last_segment };
+
+        let unused = (SEGMENT_SIZE - segment.len()) as u8;
+        segment_frame(
+            (CCS_DOWNLOAD_SEGMENT << 5) | (unused << 1) | (last_segment as u8),
+            self.toggle,
+            segment,
+        )
+    }
+}
+
+fn request(node_id: u8, data: Vec<u8>) -> CanMessage {
+    CanMessage::from_node_id(node_id, Cob::SdoRx, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::cob::Cob;
+    use crate::message::CanMessage;
+    use crate::od::ObjectValue;
+    use crate::service::sdo_client::*;
+
+    fn respond(client: &mut SdoClient, data: Vec<u8>) -> Option<Vec<u8>> {
+        client
+            .process(CanMessage::from_node_id(0x5, Cob::SdoTx, data))
+            .map(|msg| msg.data().clone())
+    }
+
+    #[test]
+    fn test_expedited_upload() {
+        let mut client = SdoClient::new();
+
+        let msg = client.upload(0x5, 0x1017, 0x00).unwrap();
+        assert_eq!(msg.cob(), Cob::SdoRx);
+        assert_eq!(msg.node_id(), 0x5);
+        assert_eq!(*msg.data(), vec![0x40, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+
+        let next = respond(&mut client, vec![0x4B, 0x17, 0x10, 0x00, 0xE8, 0x03, 0, 0]);
+        assert_eq!(next, None);
+        assert_eq!(
+            client.fetch_results(),
+            vec![SdoClientResult {
+                node_id: 0x5,
+                index: 0x1017,
+                sub_index: 0x00,
+                outcome: SdoOutcome::Uploaded(vec![0xE8, 0x03]),
+            }]
+        );
+        assert!(client.fetch_results().is_empty());
+    }
+
+    #[test]
+    fn test_segmented_upload() {
+        let mut client = SdoClient::new();
+        client.upload(0x5, 0x1008, 0x00).unwrap();
+
+        let next = respond(&mut client, vec![0x41, 0x08, 0x10, 0x00, 0x0A, 0, 0, 0]);
+        assert_eq!(next, Some(vec![0x60, 0, 0, 0, 0, 0, 0, 0]));
+
+        let next = respond(
+            &mut client,
+            vec![0x00, b'0', b'1', b'2', b'3', b'4', b'5', b'6'],
+        );
+        assert_eq!(next, Some(vec![0x70, 0, 0, 0, 0, 0, 0, 0]));
+
+        let next = respond(&mut client, vec![0x19, b'7', b'8', b'9', 0, 0, 0, 0]);
+        assert_eq!(next, None);
+        assert_eq!(
+            client.fetch_results()[0].outcome,
+            SdoOutcome::Uploaded(b"0123456789".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_expedited_download() {
+        let mut client = SdoClient::new();
+
+        let msg = client
+            .download(0x5, 0x1017, 0x00, ObjectValue::Unsigned16(500))
+            .unwrap();
+        assert_eq!(*msg.data(), vec![0x2B, 0x17, 0x10, 0x00, 0xF4, 0x01, 0, 0]);
+
+        let next = respond(&mut client, vec![0x60, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+        assert_eq!(next, None);
+        assert_eq!(client.fetch_results()[0].outcome, SdoOutcome::Downloaded);
+    }
+
+    #[test]
+    fn test_segmented_download() {
+        let mut client = SdoClient::new();
+
+        let msg = client
+            .download(
+                0x5,
+                0x2000,
+                0x01,
+                ObjectValue::VisibleString(String::from("0123456789")),
+            )
+            .unwrap();
+        assert_eq!(*msg.data(), vec![0x21, 0x00, 0x20, 0x01, 0x0A, 0, 0, 0]);
+
+        let next = respond(&mut client, vec![0x60, 0x00, 0x20, 0x01, 0, 0, 0, 0]);
+        assert_eq!(
+            next,
+            Some(vec![0x00, b'0', b'1', b'2', b'3', b'4', b'5', b'6'])
+        );
+
+        let next = respond(&mut client, vec![0x20, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(next, Some(vec![0x19, b'7', b'8', b'9', 0, 0, 0, 0]));
+
+        let next = respond(&mut client, vec![0x30, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(next, None);
+        assert_eq!(client.fetch_results()[0].outcome, SdoOutcome::Downloaded);
+    }
+
+    #[test]
+    fn test_download_empty_value() {
+        let mut client = SdoClient::new();
+
+        let msg = client
+            .download(0x5, 0x2000, 0x01, ObjectValue::VisibleString(String::new()))
+            .unwrap();
+        assert_eq!(*msg.data(), vec![0x21, 0x00, 0x20, 0x01, 0x00, 0, 0, 0]);
+
+        let next = respond(&mut client, vec![0x60, 0x00, 0x20, 0x01, 0, 0, 0, 0]);
+        assert_eq!(next, Some(vec![0x0F, 0, 0, 0, 0, 0, 0, 0]));
+
+        let next = respond(&mut client, vec![0x20, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(next, None);
+        assert_eq!(client.fetch_results()[0].outcome, SdoOutcome::Downloaded);
+    }
+
+    #[test]
+    fn test_aborted_by_server() {
+        let mut client = SdoClient::new();
+        client.upload(0x5, 0x3000, 0x00).unwrap();
+
+        let next = respond(
+            &mut client,
+            vec![0x80, 0x00, 0x30, 0x00, 0x00, 0x00, 0x02, 0x06],
+        );
+        assert_eq!(next, None);
+        assert_eq!(
+            client.fetch_results()[0].outcome,
+            SdoOutcome::Aborted(0x06020000)
+        );
+    }
+
+    #[test]
+    fn test_toggle_error_aborts() {
+        let mut client = SdoClient::new();
+        client.upload(0x5, 0x1008, 0x00).unwrap();
+        respond(&mut client, vec![0x41, 0x08, 0x10, 0x00, 0x0A, 0, 0, 0]);
+
+        let next = respond(&mut client, vec![0x10, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            next,
+            Some(vec![0x80, 0x08, 0x10, 0x00, 0x00, 0x00, 0x03, 0x05])
+        );
+        assert_eq!(
+            client.fetch_results()[0].outcome,
+            SdoOutcome::Aborted(0x05030000)
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut client = SdoClient::new();
+        client.set_timeout(Duration::from_millis(100));
+        client.upload(0x5, 0x1017, 0x00).unwrap();
+
+        assert!(client.update(Duration::from_millis(60)).is_empty());
+        let msgs = client.update(Duration::from_millis(60));
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            *msgs[0].data(),
+            vec![0x80, 0x17, 0x10, 0x00, 0x00, 0x00, 0x04, 0x05]
+        );
+        assert_eq!(client.fetch_results()[0].outcome, SdoOutcome::TimedOut);
+    }
+
+    #[test]
+    fn test_response_restarts_timeout() {
+        let mut client = SdoClient::new();
+        client.set_timeout(Duration::from_millis(100));
+        client.upload(0x5, 0x1008, 0x00).unwrap();
+
+        client.update(Duration::from_millis(60));
+        respond(&mut client, vec![0x41, 0x08, 0x10, 0x00, 0x0A, 0, 0, 0]);
+        assert!(client.update(Duration::from_millis(60)).is_empty());
+    }
+
+    #[test]
+    fn test_busy() {
+        let mut client = SdoClient::new();
+        client.upload(0x5, 0x1017, 0x00).unwrap();
+
+        assert_eq!(
+            client.upload(0x5, 0x1018, 0x01).err(),
+            Some(SdoClientError::Busy)
+        );
+        assert!(client.upload(0x6, 0x1018, 0x01).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_node_id() {
+        let mut client = SdoClient::new();
+        assert_eq!(
+            client.upload(0x0, 0x1017, 0x00).err(),
+            Some(SdoClientError::InvalidNodeId)
+        );
+        assert_eq!(
+            client.upload(0x80, 0x1017, 0x00).err(),
+            Some(SdoClientError::InvalidNodeId)
+        );
+    }
+
+    #[test]
+    fn test_response_from_unknown_node_is_ignored() {
+        let mut client = SdoClient::new();
+        let next = respond(&mut client, vec![0x60, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+        assert_eq!(next, None);
+        assert!(client.fetch_results().is_empty());
+    }
+}
@@ -0,0 +1,1386 @@
+use std::time::Duration;
+
+use crate::cob::Cob;
+use crate::message::CanMessage;
+use crate::od::{DataType, ObjectDictionary, ObjectDictionaryError};
+use crate::service::sdo::*;
+
+const CS_BLOCK_INITIATE: u8 = 0x0;
+const CS_BLOCK_END: u8 = 0x1;
+const CS_BLOCK_ACK: u8 = 0x2;
+const CS_BLOCK_START: u8 = 0x3;
+
+const ABORT_INVALID_BLOCK_SIZE: u32 = 0x0504_0002;
+const ABORT_INVALID_SEQUENCE_NUMBER: u32 = 0x0504_0003;
+const ABORT_CRC_ERROR: u32 = 0x0504_0004;
+
+const MAX_BLOCK_SIZE: u8 = 127;
+
+enum Transfer {
+    Idle,
+    Download {
+        index: u16,
+        sub_index: u8,
+        toggle: bool,
+        size: Option<usize>,
+        buffer: Vec<u8>,
+    },
+    Upload {
+        index: u16,
+        sub_index: u8,
+        toggle: bool,
+        data: Vec<u8>,
+        offset: usize,
+    },
+    BlockDownload {
+        index: u16,
+        sub_index: u8,
+        crc_enabled: bool,
+        size: Option<usize>,
+        buffer: Vec<u8>,
+        sequence: u8,
+        last_segment_received: bool,
+        receiving: bool,
+    },
+    BlockUpload {
+        index: u16,
+        sub_index: u8,
+        crc_enabled: bool,
+        data: Vec<u8>,
+        block_size: u8,
+        acknowledged: usize,
+        state: BlockUploadState,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BlockUploadState {
+    Initiated,
+    Sending,
+    Ending,
+}
+
+impl Transfer {
+    fn object(&self) -> Option<(u16, u8)> {
+        match *self {
+            Transfer::Idle => None,
+            Transfer::Download {
+                index, sub_index, ..
+            }
+            | Transfer::Upload {
+                index, sub_index, ..
+            }
+            | Transfer::BlockDownload {
+                index, sub_index, ..
+            }
+            | Transfer::BlockUpload {
+                index, sub_index, ..
+            } => Some((index, sub_index)),
+        }
+    }
+}
+
+pub struct SdoServer {
+    node_id: u8,
+    transfer: Transfer,
+    timeout: Duration,
+    elapsed: Duration,
+}
+
+impl SdoServer {
+    pub fn new(node_id: u8) -> SdoServer {
+        SdoServer {
+            node_id,
+            transfer: Transfer::Idle,
+            timeout: DEFAULT_TIMEOUT,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn reset(&mut self) {
+        self.transfer = Transfer::Idle;
+    }
+
+    pub fn update(&mut self, dt: Duration) -> Option<CanMessage> {
+        let (index, sub_index) = self.transfer.object()?;
+
+        self.elapsed += dt;
+        if self.elapsed < self.timeout {
+            return None;
+        }
+
+        self.transfer = Transfer::Idle;
+        Some(CanMessage::from_node_id(
+            self.node_id,
+            Cob::SdoTx,
+            abort_frame(index, sub_index, ABORT_TIMEOUT),
+        ))
+    }
+
+    pub fn process(
+        &mut self,
+        od: &mut ObjectDictionary,
+        can_message: CanMessage,
+    ) -> Vec<CanMessage> {
+        if !is_message_valid(self.node_id, &can_message) {
+            return Vec::new();
+        }
+        self.elapsed = Duration::from_secs(0);
+
+        let data = can_message.data();
+        let (index, sub_index) = match self.transfer.object() {
+            Some(object) if !is_initiate_request(data[0]) || self.is_receiving_block() => object,
+            _ => (u16::from_le_bytes([data[1], data[2]]), data[3]),
+        };
+
+        let is_abort = data[0] == CCS_ABORT << 5;
+        let response = if self.is_receiving_block() && !is_abort {
+            self.block_download_segment(data)
+        } else {
+            match data[0] >> 5 {
+                CCS_DOWNLOAD_SEGMENT => self.download_segment(od, data).map(|r| vec![r]),
+                CCS_INITIATE_DOWNLOAD => self
+                    .initiate_download(od, index, sub_index, data)
+                    .map(|r| vec![r]),
+                CCS_INITIATE_UPLOAD => self.initiate_upload(od, index, sub_index).map(|r| vec![r]),
+                CCS_UPLOAD_SEGMENT => self.upload_segment(data).map(|r| vec![r]),
+                CCS_BLOCK_UPLOAD => match data[0] & 0x3 {
+                    CS_BLOCK_INITIATE => self.initiate_block_upload(od, index, sub_index, data),
+                    CS_BLOCK_START => self.start_block_upload(),
+                    CS_BLOCK_ACK => self.block_upload_ack(data),
+                    CS_BLOCK_END => self.end_block_upload(),
+                    _ => Err(ABORT_COMMAND_SPECIFIER_INVALID),
+                },
+                CCS_BLOCK_DOWNLOAD => match data[0] & 0x1 {
+                    CS_BLOCK_INITIATE => self
+                        .initiate_block_download(od, index, sub_index, data)
+                        .map(|r| vec![r]),
+                    CS_BLOCK_END => self.end_block_download(od, data).map(|r| vec![r]),
+                    _ => Err(ABORT_COMMAND_SPECIFIER_INVALID),
+                },
+                CCS_ABORT => {
+                    self.transfer = Transfer::Idle;
+                    return Vec::new();
+                }
+                _ => Err(ABORT_COMMAND_SPECIFIER_INVALID),
+            }
+        };
+
+        let responses = match response {
+            Ok(responses) => responses,
+            Err(abort_code) => {
+                self.transfer = Transfer::Idle;
+                vec![abort_frame(index, sub_index, abort_code)]
+            }
+        };
+        responses
+            .into_iter()
+            .map(|data| CanMessage::from_node_id(self.node_id, Cob::SdoTx, data))
+            .collect()
+    }
+
+    fn is_receiving_block(&self) -> bool {
+        matches!(
+            self.transfer,
+            Transfer::BlockDownload {
+                receiving: true,
+                ..
+            }
+        )
+    }
+
+    fn initiate_download(
+        &mut self,
+        od: &mut ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, u32> {
+        let data_type = download_type(od, index, sub_index)?;
+
+        let expedited = (data[0] & 0x2) != 0;
+        let size_indicated = (data[0] & 0x1) != 0;
+        if expedited {
+            let size = if size_indicated {
+                4 - ((data[0] >> 2) & 0x3) as usize
+            } else {
+                4
+            };
+            let value = decode_value(data_type, &data[4..4 + size], size_indicated)?;
+            od.remote_write(index, sub_index, value)
+                .map_err(|e| e.abort_code())?;
+            self.transfer = Transfer::Idle;
+        } else {
+            let size = if size_indicated {
+                Some(u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize)
+            } else {
+                None
+            };
+            self.transfer = Transfer::Download {
+                index,
+                sub_index,
+                toggle: false,
+                size,
+                buffer: Vec::new(),
+            };
+        }
+
+        Ok(initiate_frame(0x60, index, sub_index, &[]))
+    }
+
+    fn download_segment(&mut self, od: &mut ObjectDictionary, data: &[u8]) -> Result<Vec<u8>, u32> {
+        let (index, sub_index, toggle, size, buffer) = match &mut self.transfer {
+            Transfer::Download {
+                index,
+                sub_index,
+                toggle,
+                size,
+                buffer,
+            } => (*index, *sub_index, toggle, *size, buffer),
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        };
+
+        let segment_toggle = (data[0] & 0x10) != 0;
+        if segment_toggle != *toggle {
+            return Err(ABORT_TOGGLE_BIT_NOT_ALTERNATED);
+        }
+        *toggle = !*toggle;
+
+        let unused = ((data[0] >> 1) & 0x7) as usize;
+        buffer.extend_from_slice(&data[1..1 + SEGMENT_SIZE - unused]);
+
+        let last_segment = (data[0] & 0x1) != 0;
+        if last_segment {
+            if size.is_some_and(|size| size != buffer.len()) {
+                return Err(ABORT_LENGTH_MISMATCH);
+            }
+            let data_type = download_type(od, index, sub_index)?;
+            let value = decode_value(data_type, buffer, true)?;
+            od.remote_write(index, sub_index, value)
+                .map_err(|e| e.abort_code())?;
+            self.transfer = Transfer::Idle;
+        }
+
+        Ok(segment_frame(0x20, segment_toggle, &[]))
+    }
+
+    fn initiate_upload(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<Vec<u8>, u32> {
+        let value = od
+            .remote_read(index, sub_index)
+            .map_err(|e| e.abort_code())?;
+        let bytes = value.to_bytes();
+
+        if (1..=4).contains(&bytes.len()) {
+            self.transfer = Transfer::Idle;
+            let unused = (4 - bytes.len()) as u8;
+            Ok(initiate_frame(
+                0x43 | (unused << 2),
+                index,
+                sub_index,
+                &bytes,
+            ))
+        } else {
+            let size = (bytes.len() as u32).to_le_bytes();
+            self.transfer = Transfer::Upload {
+                index,
+                sub_index,
+                toggle: false,
+                data: bytes,
+                offset: 0,
+            };
+            Ok(initiate_frame(0x41, index, sub_index, &size))
+        }
+    }
+
+    fn upload_segment(&mut self, data: &[u8]) -> Result<Vec<u8>, u32> {
+        let (toggle, bytes, offset) = match &mut self.transfer {
+            Transfer::Upload {
+                toggle,
+                data,
+                offset,
+                ..
+            } => (toggle, data, offset),
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        };
+
+        let segment_toggle = (data[0] & 0x10) != 0;
+        if segment_toggle != *toggle {
+            return Err(ABORT_TOGGLE_BIT_NOT_ALTERNATED);
+        }
+        *toggle = !*toggle;
+
+        let end = usize::min(*offset + SEGMENT_SIZE, bytes.len());
+        let segment = bytes[*offset..end].to_vec();
+        *offset = end;
+
+        let last_segment = end == bytes.len();
+        if last_segment {
+            self.transfer = Transfer::Idle;
+        }
+
+        let unused = (SEGMENT_SIZE - segment.len()) as u8;
+        let command = (unused << 1) | (last_segment as u8);
+        Ok(segment_frame(command, segment_toggle, &segment))
+    }
+
+    fn initiate_block_download(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<Vec<u8>, u32> {
+        download_type(od, index, sub_index)?;
+
+        let size_indicated = (data[0] & 0x2) != 0;
+        let size = if size_indicated {
+            Some(u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize)
+        } else {
+            None
+        };
+        self.transfer = Transfer::BlockDownload {
+            index,
+            sub_index,
+            crc_enabled: (data[0] & 0x4) != 0,
+            size,
+            buffer: Vec::new(),
+            sequence: 0,
+            last_segment_received: false,
+            receiving: true,
+        };
+
+        let mut response = initiate_frame(0xA4, index, sub_index, &[]);
+        response[4] = MAX_BLOCK_SIZE;
+        Ok(response)
+    }
+
+    fn block_download_segment(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>, u32> {
+        let (buffer, sequence, last_segment_received, receiving) = match &mut self.transfer {
+            Transfer::BlockDownload {
+                buffer,
+                sequence,
+                last_segment_received,
+                receiving,
+                ..
+            } => (buffer, sequence, last_segment_received, receiving),
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        };
+
+        let segment_sequence = data[0] & 0x7F;
+        let last_segment = (data[0] & 0x80) != 0;
+        if segment_sequence == 0 {
+            return Err(ABORT_INVALID_SEQUENCE_NUMBER);
+        }
+
+        // Segments following a lost one are dropped, the client retransmits
+        // them in the next sub-block starting after the acknowledged sequence.
+        if segment_sequence == *sequence + 1 {
+            *sequence = segment_sequence;
+            buffer.extend_from_slice(&data[1..]);
+            *last_segment_received = last_segment;
+        }
+
+        if last_segment || segment_sequence == MAX_BLOCK_SIZE {
+            let acknowledged = *sequence;
+            *sequence = 0;
+            *receiving = !*last_segment_received;
+            Ok(vec![vec![
+                0xA2,
+                acknowledged,
+                MAX_BLOCK_SIZE,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ]])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn end_block_download(
+        &mut self,
+        od: &mut ObjectDictionary,
+        data: &[u8],
+    ) -> Result<Vec<u8>, u32> {
+        let (index, sub_index, crc_enabled, size, buffer) = match &mut self.transfer {
+            Transfer::BlockDownload {
+                index,
+                sub_index,
+                crc_enabled,
+                size,
+                buffer,
+                receiving: false,
+                ..
+            } => (*index, *sub_index, *crc_enabled, *size, buffer),
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        };
+
+        let unused = ((data[0] >> 2) & 0x7) as usize;
+        let length = buffer.len().saturating_sub(unused);
+        buffer.truncate(length);
+
+        if size.is_some_and(|size| size != buffer.len()) {
+            return Err(ABORT_LENGTH_MISMATCH);
+        }
+        if crc_enabled && crc16(buffer) != u16::from_le_bytes([data[1], data[2]]) {
+            return Err(ABORT_CRC_ERROR);
+        }
+
+        let data_type = download_type(od, index, sub_index)?;
+        let value = decode_value(data_type, buffer, true)?;
+        od.remote_write(index, sub_index, value)
+            .map_err(|e| e.abort_code())?;
+        self.transfer = Transfer::Idle;
+
+        Ok(vec![0xA1, 0, 0, 0, 0, 0, 0, 0])
+    }
+
+    fn initiate_block_upload(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        data: &[u8],
+    ) -> Result<Vec<Vec<u8>>, u32> {
+        let block_size = data[4];
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+            return Err(ABORT_INVALID_BLOCK_SIZE);
+        }
+
+        let value = od
+            .remote_read(index, sub_index)
+            .map_err(|e| e.abort_code())?;
+        let bytes = value.to_bytes();
+
+        // Protocol switch threshold, small objects are cheaper to move with
+        // the regular upload protocol.
+        let threshold = data[5] as usize;
+        if threshold != 0 && bytes.len() <= threshold {
+            return self.initiate_upload(od, index, sub_index).map(|r| vec![r]);
+        }
+
+        let size = (bytes.len() as u32).to_le_bytes();
+        self.transfer = Transfer::BlockUpload {
+            index,
+            sub_index,
+            crc_enabled: (data[0] & 0x4) != 0,
+            data: bytes,
+            block_size,
+            acknowledged: 0,
+            state: BlockUploadState::Initiated,
+        };
+        Ok(vec![initiate_frame(0xC6, index, sub_index, &size)])
+    }
+
+    fn start_block_upload(&mut self) -> Result<Vec<Vec<u8>>, u32> {
+        match &mut self.transfer {
+            Transfer::BlockUpload { state, .. } if *state == BlockUploadState::Initiated => {
+                *state = BlockUploadState::Sending;
+            }
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        }
+        Ok(self.block_upload_segments())
+    }
+
+    fn block_upload_ack(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>, u32> {
+        let (bytes, crc_enabled, block_size, acknowledged, state) = match &mut self.transfer {
+            Transfer::BlockUpload {
+                data,
+                crc_enabled,
+                block_size,
+                acknowledged,
+                state,
+                ..
+            } if *state == BlockUploadState::Sending => {
+                (data, *crc_enabled, block_size, acknowledged, state)
+            }
+            _ => return Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        };
+
+        let acknowledged_sequence = data[1];
+        let sent = usize::min(*block_size as usize, segment_count(bytes) - *acknowledged);
+        if acknowledged_sequence as usize > sent {
+            return Err(ABORT_INVALID_SEQUENCE_NUMBER);
+        }
+        if data[2] == 0 || data[2] > MAX_BLOCK_SIZE {
+            return Err(ABORT_INVALID_BLOCK_SIZE);
+        }
+        *acknowledged += acknowledged_sequence as usize;
+        *block_size = data[2];
+
+        if *acknowledged < segment_count(bytes) {
+            return Ok(self.block_upload_segments());
+        }
+
+        *state = BlockUploadState::Ending;
+        let unused = (segment_count(bytes) * SEGMENT_SIZE - bytes.len()) as u8;
+        let crc = if crc_enabled { crc16(bytes) } else { 0 };
+        let crc_bytes = crc.to_le_bytes();
+        Ok(vec![vec![
+            0xC1 | (unused << 2),
+            crc_bytes[0],
+            crc_bytes[1],
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]])
+    }
+
+    fn end_block_upload(&mut self) -> Result<Vec<Vec<u8>>, u32> {
+        match self.transfer {
+            Transfer::BlockUpload {
+                state: BlockUploadState::Ending,
+                ..
+            } => {
+                self.transfer = Transfer::Idle;
+                Ok(Vec::new())
+            }
+            _ => Err(ABORT_COMMAND_SPECIFIER_INVALID),
+        }
+    }
+
+    fn block_upload_segments(&self) -> Vec<Vec<u8>> {
+        let (bytes, block_size, acknowledged) = match &self.transfer {
+            Transfer::BlockUpload {
+                data,
+                block_size,
+                acknowledged,
+                ..
+            } => (data, *block_size as usize, *acknowledged),
+            _ => return Vec::new(),
+        };
+
+        let total = segment_count(bytes);
+        let count = usize::min(block_size, total - acknowledged);
+        (0..count)
+            .map(|i| {
+                let segment = acknowledged + i;
+                let start = segment * SEGMENT_SIZE;
+                let end = usize::min(start + SEGMENT_SIZE, bytes.len());
+                let last = (segment + 1 == total) as u8;
+                let mut frame = vec![(last << 7) | (i as u8 + 1), 0, 0, 0, 0, 0, 0, 0];
+                frame[1..1 + end - start].copy_from_slice(&bytes[start..end]);
+                frame
+            })
+            .collect()
+    }
+}
+
+fn segment_count(data: &[u8]) -> usize {
+    usize::max(1, data.len().div_ceil(SEGMENT_SIZE))
+}
+
+fn is_message_valid(node_id: u8, can_message: &CanMessage) -> bool {
+    (can_message.cob() == Cob::SdoRx)
+        && (can_message.node_id() == node_id)
+        && (can_message.data_length() == 8)
+}
+
+/// Data type of an entry about to be downloaded, the received data is decoded
+/// into this type.
+fn download_type(od: &ObjectDictionary, index: u16, sub_index: u8) -> Result<DataType, u32> {
+    let attributes = od
+        .attributes(index, sub_index)
+        .map_err(|e| e.abort_code())?;
+    if !attributes.access_type.is_writable() {
+        return Err(ObjectDictionaryError::ReadOnly.abort_code());
+    }
+    od.data_type(index, sub_index).map_err(|e| e.abort_code())
+}
+
+fn is_initiate_request(command: u8) -> bool {
+    match command >> 5 {
+        CCS_INITIATE_DOWNLOAD | CCS_INITIATE_UPLOAD => true,
+        CCS_BLOCK_UPLOAD => (command & 0x3) == CS_BLOCK_INITIATE,
+        CCS_BLOCK_DOWNLOAD => (command & 0x1) == CS_BLOCK_INITIATE,
+        _ => false,
+    }
+}
+
+/// CRC-16-CCITT (polynomial 0x1021, initial value 0) as used by SDO block
+/// transfers.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if (crc & 0x8000) != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::cob::Cob;
+    use crate::message::CanMessage;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::od::{AccessType, ObjectAttributes, ObjectDictionary, ObjectProvider, ObjectValue};
+    use crate::service::sdo_server::*;
+
+    fn create_od() -> ObjectDictionary {
+        let mut od = ObjectDictionary::new();
+        od.add(0x1017, 0x00, ObjectValue::Unsigned16(1000));
+        od.add(0x2000, 0x01, ObjectValue::Integer32(-5));
+        od.add(0x2001, 0x00, ObjectValue::VisibleString(String::from("ab")));
+        od
+    }
+
+    fn request(od: &mut ObjectDictionary, data: Vec<u8>) -> Option<Vec<u8>> {
+        SdoServer::new(0x4)
+            .process(od, CanMessage::from_node_id(0x4, Cob::SdoRx, data))
+            .pop()
+            .map(|msg| msg.data().clone())
+    }
+
+    fn request_with(server: &mut SdoServer, od: &mut ObjectDictionary, data: Vec<u8>) -> Vec<u8> {
+        let mut responses = request_all(server, od, data);
+        assert_eq!(responses.len(), 1);
+        responses.pop().unwrap()
+    }
+
+    fn request_all(
+        server: &mut SdoServer,
+        od: &mut ObjectDictionary,
+        data: Vec<u8>,
+    ) -> Vec<Vec<u8>> {
+        server
+            .process(od, CanMessage::from_node_id(0x4, Cob::SdoRx, data))
+            .iter()
+            .map(|msg| msg.data().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_expedited_upload() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x40, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x4B, 0x17, 0x10, 0x00, 0xE8, 0x03, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_expedited_upload_string() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x40, 0x01, 0x20, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x4B, 0x01, 0x20, 0x00, 0x61, 0x62, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_expedited_download() {
+        let mut od = create_od();
+        let response = request(
+            &mut od,
+            vec![0x23, 0x00, 0x20, 0x01, 0xFE, 0xFF, 0xFF, 0xFF],
+        );
+        assert_eq!(
+            response,
+            Some(vec![0x60, 0x00, 0x20, 0x01, 0x00, 0x00, 0x00, 0x00])
+        );
+        match od.read(0x2000, 0x01) {
+            Ok(ObjectValue::Integer32(v)) => assert_eq!(v, -2),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_expedited_download_without_size() {
+        let mut od = create_od();
+        let response = request(
+            &mut od,
+            vec![0x22, 0x17, 0x10, 0x00, 0xF4, 0x01, 0x00, 0x00],
+        );
+        assert_eq!(response.unwrap()[0], 0x60);
+        match od.read(0x1017, 0x00) {
+            Ok(ObjectValue::Unsigned16(v)) => assert_eq!(v, 500),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_download_wrong_length() {
+        let mut od = create_od();
+        let response = request(
+            &mut od,
+            vec![0x2F, 0x17, 0x10, 0x00, 0xF4, 0x00, 0x00, 0x00],
+        );
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x17, 0x10, 0x00, 0x13, 0x00, 0x07, 0x06])
+        );
+    }
+
+    #[test]
+    fn test_upload_object_does_not_exist() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x40, 0x00, 0x30, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x00, 0x30, 0x00, 0x00, 0x00, 0x02, 0x06])
+        );
+    }
+
+    #[test]
+    fn test_upload_provided_value() {
+        struct Temperature;
+
+        impl ObjectProvider for Temperature {
+            fn provide_value(&mut self, _index: u16, _sub_index: u8) -> ObjectValue {
+                ObjectValue::Integer32(21)
+            }
+        }
+
+        let mut od = create_od();
+        od.set_provider(0x2000, 0x01, Rc::new(RefCell::new(Temperature)))
+            .unwrap();
+        let response = request(&mut od, vec![0x40, 0x00, 0x20, 0x01, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x43, 0x00, 0x20, 0x01, 0x15, 0x00, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_upload_highest_sub_index() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x40, 0x00, 0x20, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x4F, 0x00, 0x20, 0x00, 0x01, 0x00, 0x00, 0x00])
+        );
+        let response = request(&mut od, vec![0x2F, 0x00, 0x20, 0x00, 0x02, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x00, 0x20, 0x00, 0x02, 0x00, 0x01, 0x06])
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_specifier() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0xE0, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x17, 0x10, 0x00, 0x01, 0x00, 0x04, 0x05])
+        );
+    }
+
+    #[test]
+    fn test_abort_request_is_not_answered() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x80, 0x17, 0x10, 0x00, 0, 0, 0, 0]);
+        assert_eq!(response, None);
+    }
+
+    #[test]
+    fn test_request_for_other_node_is_ignored() {
+        let mut od = create_od();
+        let msg = CanMessage::from_node_id(0x5, Cob::SdoRx, vec![0x40, 0x17, 0x10, 0, 0, 0, 0, 0]);
+        assert!(SdoServer::new(0x4).process(&mut od, msg).is_empty());
+    }
+
+    #[test]
+    fn test_request_with_wrong_length_is_ignored() {
+        let mut od = create_od();
+        assert_eq!(request(&mut od, vec![0x40, 0x17, 0x10, 0x00]), None);
+    }
+
+    #[test]
+    fn test_segmented_upload() {
+        let mut od = create_od();
+        od.add(
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+        );
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x40, 0x08, 0x10, 0x00, 0, 0, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0x41, 0x08, 0x10, 0x00, 0x0F, 0x00, 0x00, 0x00]
+        );
+
+        let response = request_with(&mut server, &mut od, vec![0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            vec![0x00, b'c', b'a', b'n', b'o', b'p', b'e', b'n']
+        );
+
+        let response = request_with(&mut server, &mut od, vec![0x70, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            vec![0x10, b'-', b'r', b's', b' ', b'n', b'o', b'd']
+        );
+
+        let response = request_with(&mut server, &mut od, vec![0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0x0D, b'e', 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_segmented_upload_empty_value() {
+        let mut od = create_od();
+        od.add(0x2002, 0x00, ObjectValue::VisibleString(String::new()));
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x40, 0x02, 0x20, 0x00, 0, 0, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0x41, 0x02, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+
+        let response = request_with(&mut server, &mut od, vec![0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0x0F, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_segmented_upload_toggle_error() {
+        let mut od = create_od();
+        od.add(
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+        );
+        let mut server = SdoServer::new(0x4);
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x40, 0x08, 0x10, 0x00, 0, 0, 0, 0],
+        );
+        let response = request_with(&mut server, &mut od, vec![0x70, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            vec![0x80, 0x08, 0x10, 0x00, 0x00, 0x00, 0x03, 0x05]
+        );
+    }
+
+    #[test]
+    fn test_segmented_download() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x21, 0x01, 0x20, 0x00, 0x0A, 0x00, 0x00, 0x00],
+        );
+        assert_eq!(response, vec![0x60, 0x01, 0x20, 0x00, 0, 0, 0, 0]);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x00, b'0', b'1', b'2', b'3', b'4', b'5', b'6'],
+        );
+        assert_eq!(response, vec![0x20, 0, 0, 0, 0, 0, 0, 0]);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x19, b'7', b'8', b'9', 0, 0, 0, 0],
+        );
+        assert_eq!(response, vec![0x30, 0, 0, 0, 0, 0, 0, 0]);
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "0123456789"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_segmented_download_toggle_error() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x21, 0x01, 0x20, 0x00, 0x0A, 0x00, 0x00, 0x00],
+        );
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x10, b'0', b'1', b'2', b'3', b'4', b'5', b'6'],
+        );
+        assert_eq!(
+            response,
+            vec![0x80, 0x01, 0x20, 0x00, 0x00, 0x00, 0x03, 0x05]
+        );
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "ab"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_segmented_download_size_mismatch() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x21, 0x01, 0x20, 0x00, 0x0A, 0x00, 0x00, 0x00],
+        );
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x0B, b'0', b'1', b'2', b'3', 0, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0x80, 0x01, 0x20, 0x00, 0x10, 0x00, 0x07, 0x06]
+        );
+    }
+
+    #[test]
+    fn test_segment_without_transfer() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x00, 0x00, 0x00, 0x01, 0x00, 0x04, 0x05])
+        );
+    }
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+        assert_eq!(crc16(&[]), 0x0000);
+    }
+
+    fn block_download_od() -> (SdoServer, ObjectDictionary) {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0xC6, 0x01, 0x20, 0x00, 0x10, 0x00, 0x00, 0x00],
+        );
+        assert_eq!(response, vec![0xA4, 0x01, 0x20, 0x00, 0x7F, 0, 0, 0]);
+        (server, od)
+    }
+
+    #[test]
+    fn test_block_download() {
+        let (mut server, mut od) = block_download_od();
+        let data = b"0123456789ABCDEF";
+
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x01, b'0', b'1', b'2', b'3', b'4', b'5', b'6']
+        )
+        .is_empty());
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x02, b'7', b'8', b'9', b'A', b'B', b'C', b'D']
+        )
+        .is_empty());
+        let response = request_with(&mut server, &mut od, vec![0x83, b'E', b'F', 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0xA2, 0x03, 0x7F, 0, 0, 0, 0, 0]);
+
+        let crc = crc16(data).to_le_bytes();
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0xD5, crc[0], crc[1], 0, 0, 0, 0, 0],
+        );
+        assert_eq!(response, vec![0xA1, 0, 0, 0, 0, 0, 0, 0]);
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "0123456789ABCDEF"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_block_download_aborted_by_client() {
+        let (mut server, mut od) = block_download_od();
+
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x01, b'0', b'1', b'2', b'3', b'4', b'5', b'6']
+        )
+        .is_empty());
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x80, 0x01, 0x20, 0x00, 0x00, 0x00, 0x04, 0x05]
+        )
+        .is_empty());
+        assert!(!server.is_receiving_block());
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "ab"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_block_download_retransmission() {
+        let (mut server, mut od) = block_download_od();
+        let data = b"0123456789ABCDEF";
+
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x01, b'0', b'1', b'2', b'3', b'4', b'5', b'6']
+        )
+        .is_empty());
+        let response = request_with(&mut server, &mut od, vec![0x83, b'E', b'F', 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0xA2, 0x01, 0x7F, 0, 0, 0, 0, 0]);
+
+        assert!(request_all(
+            &mut server,
+            &mut od,
+            vec![0x01, b'7', b'8', b'9', b'A', b'B', b'C', b'D']
+        )
+        .is_empty());
+        let response = request_with(&mut server, &mut od, vec![0x82, b'E', b'F', 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0xA2, 0x02, 0x7F, 0, 0, 0, 0, 0]);
+
+        let crc = crc16(data).to_le_bytes();
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0xD5, crc[0], crc[1], 0, 0, 0, 0, 0],
+        );
+        assert_eq!(response, vec![0xA1, 0, 0, 0, 0, 0, 0, 0]);
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "0123456789ABCDEF"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_block_download_crc_error() {
+        let (mut server, mut od) = block_download_od();
+
+        request_all(
+            &mut server,
+            &mut od,
+            vec![0x01, b'0', b'1', b'2', b'3', b'4', b'5', b'6'],
+        );
+        request_all(
+            &mut server,
+            &mut od,
+            vec![0x02, b'7', b'8', b'9', b'A', b'B', b'C', b'D'],
+        );
+        request_all(&mut server, &mut od, vec![0x83, b'E', b'F', 0, 0, 0, 0, 0]);
+
+        let response = request_with(&mut server, &mut od, vec![0xD5, 0x12, 0x34, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            vec![0x80, 0x01, 0x20, 0x00, 0x04, 0x00, 0x04, 0x05]
+        );
+
+        match od.read(0x2001, 0x00) {
+            Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "ab"),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_block_upload() {
+        let mut od = create_od();
+        od.add(
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+        );
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0xA4, 0x08, 0x10, 0x00, 0x02, 0x00, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0xC6, 0x08, 0x10, 0x00, 0x0F, 0x00, 0x00, 0x00]
+        );
+
+        let responses = request_all(&mut server, &mut od, vec![0xA3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            responses,
+            vec![
+                vec![0x01, b'c', b'a', b'n', b'o', b'p', b'e', b'n'],
+                vec![0x02, b'-', b'r', b's', b' ', b'n', b'o', b'd'],
+            ]
+        );
+
+        let responses = request_all(&mut server, &mut od, vec![0xA2, 0x02, 0x02, 0, 0, 0, 0, 0]);
+        assert_eq!(responses, vec![vec![0x81, b'e', 0, 0, 0, 0, 0, 0]]);
+
+        let crc = crc16(b"canopen-rs node").to_le_bytes();
+        let response = request_with(&mut server, &mut od, vec![0xA2, 0x01, 0x02, 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0xD9, crc[0], crc[1], 0, 0, 0, 0, 0]);
+
+        assert!(request_all(&mut server, &mut od, vec![0xA1, 0, 0, 0, 0, 0, 0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_block_upload_retransmission() {
+        let mut od = create_od();
+        od.add(
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+        );
+        let mut server = SdoServer::new(0x4);
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0xA4, 0x08, 0x10, 0x00, 0x7F, 0x00, 0, 0],
+        );
+        let responses = request_all(&mut server, &mut od, vec![0xA3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(responses.len(), 3);
+
+        let responses = request_all(&mut server, &mut od, vec![0xA2, 0x01, 0x7F, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            responses,
+            vec![
+                vec![0x01, b'-', b'r', b's', b' ', b'n', b'o', b'd'],
+                vec![0x82, b'e', 0, 0, 0, 0, 0, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_upload_protocol_switch() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0xA4, 0x17, 0x10, 0x00, 0x7F, 0x04, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0x4B, 0x17, 0x10, 0x00, 0xE8, 0x03, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_block_upload_invalid_block_size() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0xA4, 0x17, 0x10, 0x00, 0x80, 0x00, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x17, 0x10, 0x00, 0x02, 0x00, 0x04, 0x05])
+        );
+    }
+
+    #[test]
+    fn test_upload_sub_index_does_not_exist() {
+        let mut od = create_od();
+        let response = request(&mut od, vec![0x40, 0x00, 0x20, 0x02, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x00, 0x20, 0x02, 0x11, 0x00, 0x09, 0x06])
+        );
+    }
+
+    #[test]
+    fn test_timeout_during_segmented_download() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+        server.set_timeout(Duration::from_millis(100));
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x21, 0x01, 0x20, 0x00, 0x0A, 0x00, 0x00, 0x00],
+        );
+        assert!(server.update(Duration::from_millis(60)).is_none());
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x00, b'0', b'1', b'2', b'3', b'4', b'5', b'6'],
+        );
+        assert!(server.update(Duration::from_millis(60)).is_none());
+
+        let msg = server.update(Duration::from_millis(40)).unwrap();
+        assert_eq!(msg.cob(), Cob::SdoTx);
+        assert_eq!(
+            *msg.data(),
+            vec![0x80, 0x01, 0x20, 0x00, 0x00, 0x00, 0x04, 0x05]
+        );
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x10, b'7', b'8', b'9', 0, 0, 0, 0],
+        );
+        assert_eq!(response[0], 0x80);
+        assert_eq!(response[4..], [0x01, 0x00, 0x04, 0x05]);
+    }
+
+    #[test]
+    fn test_no_timeout_when_idle() {
+        let mut od = create_od();
+        let mut server = SdoServer::new(0x4);
+        server.set_timeout(Duration::from_millis(100));
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x40, 0x17, 0x10, 0x00, 0, 0, 0, 0],
+        );
+        assert!(server.update(Duration::from_millis(1000)).is_none());
+    }
+
+    #[test]
+    fn test_download_read_only_object() {
+        let mut od = create_od();
+        od.add_with_attributes(
+            0x1018,
+            0x01,
+            ObjectValue::Unsigned32(0x1234),
+            ObjectAttributes {
+                access_type: AccessType::ReadOnly,
+                ..Default::default()
+            },
+        );
+        let response = request(
+            &mut od,
+            vec![0x23, 0x18, 0x10, 0x01, 0x78, 0x56, 0x34, 0x12],
+        );
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x18, 0x10, 0x01, 0x02, 0x00, 0x01, 0x06])
+        );
+        match od.read(0x1018, 0x01) {
+            Ok(ObjectValue::Unsigned32(v)) => assert_eq!(v, 0x1234),
+            _ => panic!("unexpected value"),
+        }
+    }
+
+    #[test]
+    fn test_segmented_download_const_object_aborts_on_initiate() {
+        let mut od = create_od();
+        od.add_with_attributes(
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+            ObjectAttributes {
+                access_type: AccessType::Const,
+                ..Default::default()
+            },
+        );
+        let response = request(
+            &mut od,
+            vec![0x21, 0x08, 0x10, 0x00, 0x0A, 0x00, 0x00, 0x00],
+        );
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x08, 0x10, 0x00, 0x02, 0x00, 0x01, 0x06])
+        );
+    }
+
+    #[test]
+    fn test_upload_write_only_object() {
+        let mut od = create_od();
+        od.add_with_attributes(
+            0x2002,
+            0x00,
+            ObjectValue::Unsigned8(0x1),
+            ObjectAttributes {
+                access_type: AccessType::WriteOnly,
+                ..Default::default()
+            },
+        );
+        let response = request(&mut od, vec![0x40, 0x02, 0x20, 0x00, 0, 0, 0, 0]);
+        assert_eq!(
+            response,
+            Some(vec![0x80, 0x02, 0x20, 0x00, 0x01, 0x00, 0x01, 0x06])
+        );
+
+        let response = request(&mut od, vec![0x2F, 0x02, 0x20, 0x00, 0x2, 0, 0, 0]);
+        assert_eq!(response.unwrap()[0], 0x60);
+    }
+
+    #[test]
+    fn test_segmented_upload_unsigned64() {
+        let mut od = create_od();
+        od.add(0x2100, 0x00, ObjectValue::Unsigned64(0x0102_0304_0506_0708));
+        let mut server = SdoServer::new(0x4);
+
+        let response = request_with(
+            &mut server,
+            &mut od,
+            vec![0x40, 0x00, 0x21, 0x00, 0, 0, 0, 0],
+        );
+        assert_eq!(
+            response,
+            vec![0x41, 0x00, 0x21, 0x00, 0x08, 0x00, 0x00, 0x00]
+        );
+
+        let response = request_with(&mut server, &mut od, vec![0x60, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0x00, 8, 7, 6, 5, 4, 3, 2]);
+
+        let response = request_with(&mut server, &mut od, vec![0x70, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(response, vec![0x1D, 1, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_segmented_download_domain() {
+        let mut od = create_od();
+        od.add(0x1F50, 0x01, ObjectValue::Domain(Vec::new()));
+        let mut server = SdoServer::new(0x4);
+
+        request_with(
+            &mut server,
+            &mut od,
+            vec![0x21, 0x50, 0x1F, 0x01, 0x09, 0x00, 0x00, 0x00],
+        );
+        request_with(&mut server, &mut od, vec![0x00, 1, 2, 3, 4, 5, 6, 7]);
+        request_with(&mut server, &mut od, vec![0x1B, 8, 9, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            od.read(0x1F50, 0x01),
+            Ok(ObjectValue::Domain(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]))
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::message::CanMessage;
+use crate::od::{ObjectDictionary, ObjectSubscriber, ObjectValue};
+use crate::service::pdo::*;
+
+/// Entries written since the TPDOs last looked for events.
+#[derive(Default)]
+struct ChangedObjects {
+    changed: HashSet<(u16, u8)>,
+}
+
+impl ObjectSubscriber for ChangedObjects {
+    fn object_updated(&mut self, index: u16, sub_index: u8, _value: &ObjectValue) {
+        self.changed.insert((index, sub_index));
+    }
+}
+
+#[derive(Default)]
+struct TpdoState {
+    /// A mapped object changed since the last transmission.
+    event: bool,
+    sync_count: u8,
+    since_transmission: Duration,
+    /// Time left before an event-driven TPDO may be sent again.
+    inhibited: Duration,
+    /// Data of the last transmission, writes that leave it unchanged are not
+    /// events.
+    transmitted: Option<Vec<u8>>,
+    /// Data sampled at the last SYNC for a type 252 TPDO.
+    sampled: Option<Vec<u8>>,
+}
+
+/// Produces transmit PDOs from the communication (0x1800 - 0x19FF) and
+/// mapping (0x1A00 - 0x1BFF) parameters in the dictionary.
+pub struct TransmitPdos {
+    valid: ValidPdos,
+    changes: Rc<RefCell<ChangedObjects>>,
+    states: BTreeMap<u16, TpdoState>,
+}
+
+impl Default for TransmitPdos {
+    fn default() -> Self {
+        TransmitPdos::new()
+    }
+}
+
+impl TransmitPdos {
+    pub fn new() -> TransmitPdos {
+        TransmitPdos {
+            valid: ValidPdos::new(TPDO_COMMUNICATION),
+            changes: Rc::new(RefCell::new(ChangedObjects::default())),
+            states: BTreeMap::new(),
+        }
+    }
+
+    /// Watches every entry for writes, including entries added later.
+    pub fn subscribe(&self, od: &mut ObjectDictionary) {
+        od.subscribe_range(0x0000..=0xFFFF, self.changes.clone());
+        self.valid.subscribe(od);
+    }
+
+    pub fn reset(&mut self) {
+        self.valid.invalidate();
+        self.changes.borrow_mut().changed.clear();
+        self.states.clear();
+    }
+
+    /// Transmits the synchronous TPDOs due on this SYNC. Acyclic ones
+    /// (type 0) are only sent when a mapped object changed.
+    pub fn sync(&mut self, od: &ObjectDictionary) -> Vec<CanMessage> {
+        self.collect_events(od);
+
+        let mut messages = Vec::new();
+        for &(index, communication) in self.valid.get(od).iter() {
+            let state = self.states.entry(index).or_default();
+            let due = match communication.transmission_type {
+                0 => state.event,
+                1..=TRANSMISSION_SYNCHRONOUS_MAX => {
+                    state.sync_count += 1;
+                    state.sync_count >= communication.transmission_type
+                }
+                TRANSMISSION_RTR_SYNCHRONOUS => {
+                    state.sampled = pdo_data(od, index);
+                    false
+                }
+                _ => false,
+            };
+            if due {
+                state.sync_count = 0;
+                state.event = false;
+                messages.extend(transmit(od, index, &communication, state));
+            }
+        }
+        messages
+    }
+
+    /// Transmits the event-driven TPDOs whose mapped objects changed or whose
+    /// event timer elapsed. While the inhibit time runs, events are held back
+    /// and sent as a single frame with the latest values once it has passed.
+    pub fn update(&mut self, od: &ObjectDictionary, dt: Duration) -> Vec<CanMessage> {
+        self.collect_events(od);
+
+        let mut messages = Vec::new();
+        for &(index, communication) in self.valid.get(od).iter() {
+            if !communication.is_event_driven() {
+                continue;
+            }
+            let state = self.states.entry(index).or_default();
+            state.since_transmission += dt;
+            state.inhibited = state.inhibited.saturating_sub(dt);
+            if state.inhibited > Duration::from_secs(0) {
+                continue;
+            }
+            let timer_elapsed = communication.event_timer != 0
+                && state.since_transmission
+                    >= Duration::from_millis(communication.event_timer as u64);
+            if state.event || timer_elapsed {
+                state.event = false;
+                state.since_transmission = Duration::from_secs(0);
+                state.inhibited = communication.inhibit_duration();
+                messages.extend(transmit(od, index, &communication, state));
+            }
+        }
+        messages
+    }
+
+    /// Answers a remote request for a TPDO that allows RTR. Type 252 TPDOs
+    /// send the data sampled at the last SYNC, none before the first one,
+    /// all others the current values.
+    pub fn remote_request(
+        &mut self,
+        od: &ObjectDictionary,
+        can_message: &CanMessage,
+    ) -> Vec<CanMessage> {
+        let mut messages = Vec::new();
+        for &(index, communication) in self.valid.get(od).iter() {
+            if communication.can_id() != can_message.can_id() || !communication.is_rtr_allowed() {
+                continue;
+            }
+            let state = self.states.entry(index).or_default();
+            let data = match communication.transmission_type {
+                TRANSMISSION_RTR_SYNCHRONOUS => state.sampled.clone(),
+                _ => pdo_data(od, index),
+            };
+            if let Some(data) = data {
+                state.event = false;
+                state.transmitted = Some(data.clone());
+                messages.push(CanMessage::from_can_id(communication.can_id(), data));
+            }
+        }
+        messages
+    }
+
+    /// Flags the TPDOs mapping an object written since the last call, unless
+    /// the write left their data as last transmitted.
+    fn collect_events(&mut self, od: &ObjectDictionary) {
+        let changed = std::mem::take(&mut self.changes.borrow_mut().changed);
+        if changed.is_empty() {
+            return;
+        }
+        for &(index, _) in self.valid.get(od).iter() {
+            let mapping = mapping(od, index + PDO_COUNT).unwrap_or_default();
+            if mapping
+                .iter()
+                .any(|m| changed.contains(&(m.index, m.sub_index)))
+            {
+                let state = self.states.entry(index).or_default();
+                if state.transmitted != pdo_data(od, index) {
+                    state.event = true;
+                }
+            }
+        }
+    }
+}
+
+fn transmit(
+    od: &ObjectDictionary,
+    communication_index: u16,
+    communication: &Communication,
+    state: &mut TpdoState,
+) -> Option<CanMessage> {
+    let data = pdo_data(od, communication_index)?;
+    state.transmitted = Some(data.clone());
+    Some(CanMessage::from_can_id(communication.can_id(), data))
+}
+
+fn pdo_data(od: &ObjectDictionary, communication_index: u16) -> Option<Vec<u8>> {
+    let mapping = mapping(od, communication_index + PDO_COUNT).ok()?;
+    pack(od, &mapping).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::od::{ObjectDictionary, ObjectValue};
+    use crate::service::tpdo::*;
+
+    fn create_od(transmission_type: u8, event_timer: u16) -> ObjectDictionary {
+        create_od_with_inhibit_time(transmission_type, 0, event_timer)
+    }
+
+    fn create_od_with_inhibit_time(
+        transmission_type: u8,
+        inhibit_time: u16,
+        event_timer: u16,
+    ) -> ObjectDictionary {
+        let mut od = ObjectDictionary::new();
+        od.add(0x2000, 0x00, ObjectValue::Unsigned16(0x1234));
+        od.add(0x2001, 0x00, ObjectValue::Unsigned16(0));
+        od.add(0x1800, 0x01, ObjectValue::Unsigned32(0x185));
+        od.add(0x1800, 0x02, ObjectValue::Unsigned8(transmission_type));
+        od.add(0x1800, 0x03, ObjectValue::Unsigned16(inhibit_time));
+        od.add(0x1800, 0x05, ObjectValue::Unsigned16(event_timer));
+        od.add(0x1A00, 0x00, ObjectValue::Unsigned8(1));
+        od.add(0x1A00, 0x01, ObjectValue::Unsigned32(0x2000_0010));
+        od
+    }
+
+    fn data(messages: Vec<CanMessage>) -> Vec<(u16, Vec<u8>)> {
+        messages
+            .iter()
+            .map(|msg| (msg.can_id(), msg.data().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cyclic_synchronous_tpdo() {
+        let od = create_od(2, 0);
+        let mut tpdos = TransmitPdos::new();
+
+        assert!(tpdos.sync(&od).is_empty());
+        assert_eq!(data(tpdos.sync(&od)), vec![(0x185, vec![0x34, 0x12])]);
+        assert!(tpdos.sync(&od).is_empty());
+        assert_eq!(tpdos.sync(&od).len(), 1);
+        assert!(tpdos.update(&od, Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_acyclic_synchronous_tpdo() {
+        let mut od = create_od(0, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        assert!(tpdos.sync(&od).is_empty());
+        od.write(0x2001, 0x00, ObjectValue::Unsigned16(1)).unwrap();
+        assert!(tpdos.sync(&od).is_empty());
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert!(tpdos.update(&od, Duration::from_millis(1)).is_empty());
+        assert_eq!(data(tpdos.sync(&od)), vec![(0x185, vec![0x78, 0x56])]);
+        assert!(tpdos.sync(&od).is_empty());
+    }
+
+    #[test]
+    fn test_event_driven_tpdo() {
+        let mut od = create_od(0xFF, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        assert!(tpdos.update(&od, Duration::from_secs(1)).is_empty());
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert!(tpdos.sync(&od).is_empty());
+        assert_eq!(
+            data(tpdos.update(&od, Duration::from_millis(1))),
+            vec![(0x185, vec![0x78, 0x56])]
+        );
+        assert!(tpdos.update(&od, Duration::from_millis(1)).is_empty());
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x1111))
+            .unwrap();
+        tpdos.reset();
+        assert!(tpdos.update(&od, Duration::from_millis(1)).is_empty());
+    }
+
+    #[test]
+    fn test_event_driven_tpdo_unchanged_value() {
+        let mut od = create_od(0xFF, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert_eq!(tpdos.update(&od, Duration::from_millis(1)).len(), 1);
+
+        // Writing the value that was last transmitted is not a change.
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert!(tpdos.update(&od, Duration::from_millis(1)).is_empty());
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5679))
+            .unwrap();
+        assert_eq!(tpdos.update(&od, Duration::from_millis(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_object_added_after_subscribe() {
+        let mut od = create_od(0xFF, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        od.add(0x2002, 0x00, ObjectValue::Unsigned8(0));
+        od.write(0x1A00, 0x01, ObjectValue::Unsigned32(0x2002_0008))
+            .unwrap();
+        od.write(0x2002, 0x00, ObjectValue::Unsigned8(0x42))
+            .unwrap();
+        assert_eq!(
+            data(tpdos.update(&od, Duration::from_millis(1))),
+            vec![(0x185, vec![0x42])]
+        );
+    }
+
+    #[test]
+    fn test_event_timer() {
+        let mut od = create_od(0xFE, 100);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        assert!(tpdos.update(&od, Duration::from_millis(60)).is_empty());
+        assert_eq!(tpdos.update(&od, Duration::from_millis(40)).len(), 1);
+        assert!(tpdos.update(&od, Duration::from_millis(60)).is_empty());
+
+        // An event transmission restarts the timer.
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert_eq!(tpdos.update(&od, Duration::from_millis(10)).len(), 1);
+        assert!(tpdos.update(&od, Duration::from_millis(60)).is_empty());
+        assert_eq!(tpdos.update(&od, Duration::from_millis(40)).len(), 1);
+    }
+
+    #[test]
+    fn test_inhibit_time() {
+        let mut od = create_od_with_inhibit_time(0xFF, 10, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(1)).unwrap();
+        assert_eq!(
+            data(tpdos.update(&od, Duration::from_micros(100))),
+            vec![(0x185, vec![0x01, 0x00])]
+        );
+
+        // Changes within the inhibit time are coalesced into one frame.
+        for value in 2..=4 {
+            od.write(0x2000, 0x00, ObjectValue::Unsigned16(value))
+                .unwrap();
+            assert!(tpdos.update(&od, Duration::from_micros(300)).is_empty());
+        }
+        assert_eq!(
+            data(tpdos.update(&od, Duration::from_micros(100))),
+            vec![(0x185, vec![0x04, 0x00])]
+        );
+        assert!(tpdos.update(&od, Duration::from_millis(5)).is_empty());
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(5)).unwrap();
+        assert_eq!(tpdos.update(&od, Duration::from_micros(100)).len(), 1);
+    }
+
+    #[test]
+    fn test_remote_request() {
+        let mut od = create_od(0xFD, 0);
+        let mut tpdos = TransmitPdos::new();
+        tpdos.subscribe(&mut od);
+
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert!(tpdos.update(&od, Duration::from_millis(1)).is_empty());
+        assert!(tpdos.sync(&od).is_empty());
+        assert!(tpdos
+            .remote_request(&od, &CanMessage::remote_request(0x186))
+            .is_empty());
+        assert_eq!(
+            data(tpdos.remote_request(&od, &CanMessage::remote_request(0x185))),
+            vec![(0x185, vec![0x78, 0x56])]
+        );
+
+        od.write(0x1800, 0x01, ObjectValue::Unsigned32(0x4000_0185))
+            .unwrap();
+        assert!(tpdos
+            .remote_request(&od, &CanMessage::remote_request(0x185))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_synchronous_remote_request() {
+        let mut od = create_od(0xFC, 0);
+        let mut tpdos = TransmitPdos::new();
+        let request = CanMessage::remote_request(0x185);
+
+        assert!(tpdos.remote_request(&od, &request).is_empty());
+        assert!(tpdos.sync(&od).is_empty());
+        od.write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+            .unwrap();
+        assert_eq!(
+            data(tpdos.remote_request(&od, &request)),
+            vec![(0x185, vec![0x34, 0x12])]
+        );
+        tpdos.sync(&od);
+        assert_eq!(
+            data(tpdos.remote_request(&od, &request)),
+            vec![(0x185, vec![0x78, 0x56])]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tpdo() {
+        let mut od = create_od(1, 0);
+        od.write(0x1800, 0x01, ObjectValue::Unsigned32(0x8000_0185))
+            .unwrap();
+        let mut tpdos = TransmitPdos::new();
+
+        assert!(tpdos.sync(&od).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::concise_dcf;
+use crate::od::{
+    ObjectCommand, ObjectDictionary, ObjectDictionaryError, ObjectValue, ParameterArea,
+};
+
+/// "save" in little-endian ASCII, written to 0x1010 to store parameters.
+pub const SAVE_SIGNATURE: u32 = 0x6576_6173;
+/// "load" in little-endian ASCII, written to 0x1011 to restore defaults.
+pub const LOAD_SIGNATURE: u32 = 0x6461_6F6C;
+
+/// Persistent memory for the parameters of one area, stored as a concise DCF.
+pub trait Storage {
+    fn save(&mut self, area: ParameterArea, data: &[u8]) -> io::Result<()>;
+    /// Returns `None` when nothing is stored for the area.
+    fn load(&mut self, area: ParameterArea) -> io::Result<Option<Vec<u8>>>;
+    fn clear(&mut self, area: ParameterArea) -> io::Result<()>;
+}
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    areas: HashMap<ParameterArea, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage {
+            areas: HashMap::new(),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn save(&mut self, area: ParameterArea, data: &[u8]) -> io::Result<()> {
+        self.areas.insert(area, data.to_vec());
+        Ok(())
+    }
+
+    fn load(&mut self, area: ParameterArea) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.areas.get(&area).cloned())
+    }
+
+    fn clear(&mut self, area: ParameterArea) -> io::Result<()> {
+        self.areas.remove(&area);
+        Ok(())
+    }
+}
+
+/// Keeps one file per area in `directory`.
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> FileStorage {
+        FileStorage {
+            directory: directory.into(),
+        }
+    }
+
+    fn path(&self, area: ParameterArea) -> PathBuf {
+        self.directory.join(match area {
+            ParameterArea::Communication => "communication.cdcf",
+            ParameterArea::Application => "application.cdcf",
+        })
+    }
+}
+
+impl Storage for FileStorage {
+    fn save(&mut self, area: ParameterArea, data: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.path(area), data)
+    }
+
+    fn load(&mut self, area: ParameterArea) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(area)) {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn clear(&mut self, area: ParameterArea) -> io::Result<()> {
+        match fs::remove_file(self.path(area)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The areas addressed by a sub-index of 0x1010 and 0x1011.
+pub(crate) fn areas(sub_index: u8) -> &'static [ParameterArea] {
+    match sub_index {
+        1 => &[ParameterArea::Communication, ParameterArea::Application],
+        2 => &[ParameterArea::Communication],
+        3 => &[ParameterArea::Application],
+        _ => &[],
+    }
+}
+
+/// Stores the current value of every writable entry in `area`.
+pub(crate) fn save_parameters(
+    od: &ObjectDictionary,
+    storage: &mut dyn Storage,
+    area: ParameterArea,
+) -> io::Result<()> {
+    let keys: Vec<(u16, u8)> = od
+        .entries()
+        .into_iter()
+        .filter(|(index, sub_index)| {
+            area.contains(*index)
+                && !matches!(*index, 0x1010 | 0x1011)
+                && od
+                    .attributes(*index, *sub_index)
+                    .is_ok_and(|attributes| attributes.access_type.is_writable())
+        })
+        .collect();
+    let entries = concise_dcf::entries_from(od, &keys).expect("keys are in the dictionary");
+    storage.save(area, &concise_dcf::generate(&entries))
+}
+
+pub(crate) fn load_parameters(
+    od: &mut ObjectDictionary,
+    storage: &mut dyn Storage,
+    area: ParameterArea,
+) -> io::Result<()> {
+    if let Some(data) = storage.load(area)? {
+        concise_dcf::apply_trusted(od, &data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Carries out the save and restore commands written to 0x1010 and 0x1011,
+/// a failed command reaches the writer as an SDO abort.
+#[derive(Default)]
+pub(crate) struct StoreCommands {
+    pub storage: Option<Box<dyn Storage>>,
+}
+
+impl ObjectCommand for StoreCommands {
+    fn execute(
+        &mut self,
+        od: &ObjectDictionary,
+        index: u16,
+        sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        let storage = self
+            .storage
+            .as_mut()
+            .ok_or(ObjectDictionaryError::CannotTransfer)?;
+        let result = match (index, value) {
+            (0x1010, ObjectValue::Unsigned32(SAVE_SIGNATURE)) => areas(sub_index)
+                .iter()
+                .try_for_each(|area| save_parameters(od, storage.as_mut(), *area)),
+            (0x1011, ObjectValue::Unsigned32(LOAD_SIGNATURE)) => areas(sub_index)
+                .iter()
+                .try_for_each(|area| storage.clear(*area)),
+            _ => return Err(ObjectDictionaryError::CannotTransfer),
+        };
+        result.map_err(|_| ObjectDictionaryError::HardwareError)
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::eds::{self, EdsError, Entry, Section};
+use crate::od::ObjectDictionary;
+
+/// A syntax or type error in an XML device description, `line` is 1-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XddError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl XddError {
+    fn new(line: usize, message: String) -> XddError {
+        XddError { line, message }
+    }
+}
+
+impl From<EdsError> for XddError {
+    fn from(error: EdsError) -> XddError {
+        XddError::new(error.line, error.message)
+    }
+}
+
+impl fmt::Display for XddError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for XddError {}
+
+/// Builds an object dictionary from a CiA 311 XML device description.
+/// `$NODEID` in values is replaced by `node_id`.
+pub fn parse(input: &str, node_id: u8) -> Result<ObjectDictionary, XddError> {
+    let (sections, _) = parse_document(input)?;
+    Ok(eds::build(&sections, node_id)?)
+}
+
+/// Reads an XML device configuration, returning the node ID from the
+/// `deviceCommissioning` element along with the configured dictionary.
+pub fn parse_xdc(input: &str) -> Result<(u8, ObjectDictionary), XddError> {
+    let (sections, commissioning) = parse_document(input)?;
+    let element = commissioning
+        .ok_or_else(|| XddError::new(1, String::from("missing deviceCommissioning element")))?;
+    let node_id = match element.attributes.get("nodeID").map(|id| id.parse::<u8>()) {
+        Some(Ok(node_id @ 1..=127)) => node_id,
+        _ => {
+            return Err(XddError::new(
+                element.line,
+                String::from("missing or invalid nodeID"),
+            ))
+        }
+    };
+    Ok((node_id, eds::build(&sections, node_id)?))
+}
+
+/// XDD attributes and the EDS keys they correspond to.
+const ATTRIBUTE_KEYS: [(&str, &str); 8] = [
+    ("name", "parametername"),
+    ("objectType", "objecttype"),
+    ("accessType", "accesstype"),
+    ("defaultValue", "defaultvalue"),
+    ("lowLimit", "lowlimit"),
+    ("highLimit", "highlimit"),
+    ("actualValue", "parametervalue"),
+    ("PDOmapping", "pdomapping"),
+];
+
+/// Translates `CANopenObject` and `CANopenSubObject` elements into the
+/// sections an EDS would have for the same dictionary.
+fn parse_document(input: &str) -> Result<(Vec<Section>, Option<Element>), XddError> {
+    let mut sections = Vec::new();
+    let mut commissioning = None;
+    let mut object_index = None;
+
+    for event in tokenize(input)? {
+        match event {
+            Event::Start(element) => match local_name(&element.name) {
+                "CANopenObject" => {
+                    let index = hex_attribute(&element, "index", 0xFFFF)? as u16;
+                    sections.push(to_section(format!("{:04X}", index), &element)?);
+                    object_index = Some(index);
+                }
+                "CANopenSubObject" => {
+                    let index = object_index.ok_or_else(|| {
+                        XddError::new(
+                            element.line,
+                            String::from("CANopenSubObject outside of a CANopenObject"),
+                        )
+                    })?;
+                    let sub_index = hex_attribute(&element, "subIndex", 0xFF)?;
+                    sections.push(to_section(
+                        format!("{:04X}sub{:X}", index, sub_index),
+                        &element,
+                    )?);
+                }
+                "deviceCommissioning" => commissioning = Some(element),
+                _ => {}
+            },
+            Event::End(name) => {
+                if local_name(&name) == "CANopenObject" {
+                    object_index = None;
+                }
+            }
+        }
+    }
+
+    Ok((sections, commissioning))
+}
+
+fn to_section(name: String, element: &Element) -> Result<Section, XddError> {
+    let mut entries = HashMap::new();
+    let mut insert = |key: &str, value: String| {
+        entries.insert(
+            key.to_string(),
+            Entry {
+                line: element.line,
+                value,
+            },
+        );
+    };
+
+    for (attribute, key) in ATTRIBUTE_KEYS.iter() {
+        if let Some(value) = element.attributes.get(*attribute) {
+            let value = match *key {
+                "pdomapping" if value == "no" => String::from("0"),
+                "pdomapping" => String::from("1"),
+                _ => value.clone(),
+            };
+            insert(key, value);
+        }
+    }
+    if element.attributes.contains_key("dataType") {
+        insert(
+            "datatype",
+            format!("{:#06X}", hex_attribute(element, "dataType", 0xFFFF)?),
+        );
+    }
+
+    Ok(Section {
+        name,
+        line: element.line,
+        entries,
+    })
+}
+
+fn hex_attribute(element: &Element, name: &str, max: u32) -> Result<u32, XddError> {
+    let value = element.attributes.get(name).ok_or_else(|| {
+        XddError::new(
+            element.line,
+            format!("{} is missing attribute {}", element.name, name),
+        )
+    })?;
+    match u32::from_str_radix(value, 16) {
+        Ok(number) if number <= max && !value.starts_with('+') => Ok(number),
+        _ => Err(XddError::new(
+            element.line,
+            format!("invalid {} '{}'", name, value),
+        )),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    line: usize,
+}
+
+enum Event {
+    Start(Element),
+    End(String),
+}
+
+/// Splits the document into start and end tags, self-closing elements produce
+/// both. Text, comments, CDATA and declarations are skipped.
+fn tokenize(input: &str) -> Result<Vec<Event>, XddError> {
+    let mut scanner = Scanner {
+        input,
+        position: 0,
+        line: 1,
+    };
+    let mut events = Vec::new();
+    let mut open: Vec<(String, usize)> = Vec::new();
+
+    while scanner.skip_past("<").is_some() {
+        let line = scanner.line;
+        let rest = &input[scanner.position..];
+        let (terminator, what) = if rest.starts_with("!--") {
+            ("-->", "comment")
+        } else if rest.starts_with("![CDATA[") {
+            ("]]>", "CDATA section")
+        } else if rest.starts_with('?') {
+            ("?>", "declaration")
+        } else if rest.starts_with('!') {
+            (">", "declaration")
+        } else {
+            let tag = scanner
+                .tag_body()
+                .ok_or_else(|| XddError::new(line, String::from("unterminated tag")))?;
+            if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim();
+                match open.pop() {
+                    Some((open_name, _)) if open_name == name => {}
+                    _ => {
+                        return Err(XddError::new(
+                            line,
+                            format!("unexpected closing tag </{}>", name),
+                        ))
+                    }
+                }
+                events.push(Event::End(name.to_string()));
+            } else {
+                let (tag, self_closing) = match tag.strip_suffix('/') {
+                    Some(tag) => (tag, true),
+                    None => (tag, false),
+                };
+                let element = parse_tag(tag, line)?;
+                let name = element.name.clone();
+                events.push(Event::Start(element));
+                if self_closing {
+                    events.push(Event::End(name));
+                } else {
+                    open.push((name, line));
+                }
+            }
+            continue;
+        };
+        if scanner.skip_past(terminator).is_none() {
+            return Err(XddError::new(line, format!("unterminated {}", what)));
+        }
+    }
+
+    match open.pop() {
+        Some((name, line)) => Err(XddError::new(
+            line,
+            format!("element <{}> is not closed", name),
+        )),
+        None => Ok(events),
+    }
+}
+
+fn parse_tag(tag: &str, line: usize) -> Result<Element, XddError> {
+    let invalid = || XddError::new(line, format!("invalid tag <{}>", tag.trim()));
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    if name.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut attributes = HashMap::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let equals = rest.find('=').ok_or_else(invalid)?;
+        let attribute = rest[..equals].trim();
+        let value_part = rest[equals + 1..].trim_start();
+        let quote = value_part.chars().next().ok_or_else(invalid)?;
+        if quote != '"' && quote != '\'' {
+            return Err(invalid());
+        }
+        let end = value_part[1..].find(quote).ok_or_else(invalid)? + 1;
+        let value = decode_entities(&value_part[1..end])
+            .ok_or_else(|| XddError::new(line, format!("invalid entity in {}", attribute)))?;
+        if attribute.is_empty() || attribute.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        attributes.insert(attribute.to_string(), value);
+        rest = value_part[end + 1..].trim_start();
+    }
+
+    Ok(Element {
+        name: name.to_string(),
+        attributes,
+        line,
+    })
+}
+
+fn decode_entities(text: &str) -> Option<String> {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()?
+                } else {
+                    entity.strip_prefix('#')?.parse().ok()?
+                };
+                std::char::from_u32(code)?
+            }
+        };
+        decoded.push(c);
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+    Some(decoded)
+}
+
+struct Scanner<'a> {
+    input: &'a str,
+    position: usize,
+    line: usize,
+}
+
+impl<'a> Scanner<'a> {
+    /// Moves past the next occurrence of `pattern`, returning the skipped text.
+    fn skip_past(&mut self, pattern: &str) -> Option<&'a str> {
+        let start = self.position;
+        let end = self.input[start..].find(pattern)? + start;
+        self.advance(end + pattern.len());
+        Some(&self.input[start..end])
+    }
+
+    /// Reads up to the `>` closing the current tag, ignoring any inside quotes.
+    fn tag_body(&mut self) -> Option<&'a str> {
+        let start = self.position;
+        let mut quote = None;
+        for (offset, c) in self.input[start..].char_indices() {
+            match (quote, c) {
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (Some(q), _) if q == c => quote = None,
+                (None, '>') => {
+                    self.advance(start + offset + 1);
+                    return Some(&self.input[start..start + offset]);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn advance(&mut self, position: usize) {
+        self.line += self.input[self.position..position].matches('\n').count();
+        self.position = position;
+    }
+}
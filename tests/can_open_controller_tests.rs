@@ -1,8 +1,13 @@
 extern crate canopen_rs;
 
+use std::io;
+use std::time::Duration;
+
 use canopen_rs::cob::Cob;
-use canopen_rs::controller::{CanOpenController, NmtState};
+use canopen_rs::controller::{CanOpenController, Identity, NmtState, SdoClientResult, SdoOutcome};
 use canopen_rs::message::CanMessage;
+use canopen_rs::od::{ObjectAttributes, ObjectValue, ParameterArea};
+use canopen_rs::storage::{MemoryStorage, Storage};
 
 fn is_boot_up_message(can_message: Option<CanMessage>, node_id: u8) -> bool {
     match can_message {
@@ -104,3 +109,654 @@ fn test_can_open_controller_reset_communication() {
     assert!(is_boot_up_message(controller.fetch().pop(), 0x1A));
     assert_eq!(controller.nmt_state(), NmtState::PreOperational);
 }
+
+#[test]
+fn test_can_open_controller_sdo_upload() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x01, ObjectValue::Unsigned32(0x12345678));
+
+    controller.init();
+    controller.fetch();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x40, 0x00, 0x20, 0x01, 0x0, 0x0, 0x0, 0x0],
+    ));
+
+    let msg = controller.fetch().pop().unwrap();
+    assert_eq!(msg.cob(), Cob::SdoTx);
+    assert_eq!(msg.node_id(), 0x1A);
+    assert_eq!(
+        *msg.data(),
+        vec![0x43, 0x00, 0x20, 0x01, 0x78, 0x56, 0x34, 0x12]
+    );
+}
+
+#[test]
+fn test_can_open_controller_sdo_download() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x01, ObjectValue::Unsigned16(0x0));
+
+    controller.init();
+    controller.fetch();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x2B, 0x00, 0x20, 0x01, 0x34, 0x12, 0x0, 0x0],
+    ));
+
+    let msg = controller.fetch().pop().unwrap();
+    assert_eq!(msg.data()[0], 0x60);
+    match controller.object_dictionary().read(0x2000, 0x01) {
+        Ok(ObjectValue::Unsigned16(v)) => assert_eq!(v, 0x1234),
+        _ => panic!("unexpected value"),
+    }
+}
+
+#[test]
+fn test_can_open_controller_sdo_ignored_when_stopped() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x01, ObjectValue::Unsigned8(0x0));
+
+    controller.init();
+    controller.fetch();
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x2, 0x1A]));
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x40, 0x00, 0x20, 0x01, 0x0, 0x0, 0x0, 0x0],
+    ));
+
+    assert!(controller.fetch().is_empty());
+}
+
+#[test]
+fn test_can_open_controller_sdo_client_upload() {
+    let mut controller = CanOpenController::new(0x1A);
+
+    controller.init();
+    controller.fetch();
+    controller.upload(0x05, 0x1017, 0x00).unwrap();
+
+    let msg = controller.fetch().pop().unwrap();
+    assert_eq!(msg.cob(), Cob::SdoRx);
+    assert_eq!(msg.node_id(), 0x05);
+    assert_eq!(
+        *msg.data(),
+        vec![0x40, 0x17, 0x10, 0x00, 0x0, 0x0, 0x0, 0x0]
+    );
+
+    controller.process(CanMessage::from_node_id(
+        0x05,
+        Cob::SdoTx,
+        vec![0x4B, 0x17, 0x10, 0x00, 0xE8, 0x03, 0x0, 0x0],
+    ));
+
+    assert_eq!(
+        controller.fetch_sdo_results(),
+        vec![SdoClientResult {
+            node_id: 0x05,
+            index: 0x1017,
+            sub_index: 0x00,
+            outcome: SdoOutcome::Uploaded(vec![0xE8, 0x03]),
+        }]
+    );
+}
+
+#[test]
+fn test_can_open_controller_sdo_client_download_to_server() {
+    let mut client = CanOpenController::new(0x01);
+    let mut server = CanOpenController::new(0x02);
+    server
+        .object_dictionary_mut()
+        .add(0x1008, 0x00, ObjectValue::VisibleString(String::from("")));
+
+    client.init();
+    server.init();
+    client.fetch();
+    server.fetch();
+
+    client
+        .download(
+            0x02,
+            0x1008,
+            0x00,
+            ObjectValue::VisibleString(String::from("canopen-rs node")),
+        )
+        .unwrap();
+
+    loop {
+        let requests = client.fetch();
+        if requests.is_empty() {
+            break;
+        }
+        for request in requests {
+            server.process(request);
+        }
+        for response in server.fetch() {
+            client.process(response);
+        }
+    }
+
+    assert_eq!(
+        client.fetch_sdo_results()[0].outcome,
+        SdoOutcome::Downloaded
+    );
+    match server.object_dictionary().read(0x1008, 0x00) {
+        Ok(ObjectValue::VisibleString(v)) => assert_eq!(v, "canopen-rs node"),
+        _ => panic!("unexpected value"),
+    }
+}
+
+#[test]
+fn test_can_open_controller_sdo_client_timeout() {
+    let mut controller = CanOpenController::new(0x1A);
+
+    controller.init();
+    controller.set_sdo_timeout(Duration::from_millis(500));
+    controller.upload(0x05, 0x1017, 0x00).unwrap();
+    controller.fetch();
+
+    controller.update(Duration::from_millis(499));
+    assert!(controller.fetch_sdo_results().is_empty());
+
+    controller.update(Duration::from_millis(1));
+    let msg = controller.fetch().pop().unwrap();
+    assert_eq!(
+        *msg.data(),
+        vec![0x80, 0x17, 0x10, 0x00, 0x00, 0x00, 0x04, 0x05]
+    );
+    assert_eq!(
+        controller.fetch_sdo_results()[0].outcome,
+        SdoOutcome::TimedOut
+    );
+}
+
+#[test]
+fn test_can_open_controller_sdo_server_timeout() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.object_dictionary_mut().add(
+        0x1008,
+        0x00,
+        ObjectValue::VisibleString(String::from("canopen-rs node")),
+    );
+
+    controller.init();
+    controller.set_sdo_timeout(Duration::from_millis(500));
+    controller.fetch();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x40, 0x08, 0x10, 0x00, 0x0, 0x0, 0x0, 0x0],
+    ));
+    assert_eq!(controller.fetch().pop().unwrap().data()[0], 0x41);
+
+    controller.update(Duration::from_millis(500));
+
+    let msg = controller.fetch().pop().unwrap();
+    assert_eq!(msg.cob(), Cob::SdoTx);
+    assert_eq!(
+        *msg.data(),
+        vec![0x80, 0x08, 0x10, 0x00, 0x00, 0x00, 0x04, 0x05]
+    );
+}
+
+#[test]
+fn test_can_open_controller_reset_restores_defaults() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x1017, 0x00, ObjectValue::Unsigned16(1000));
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x00, ObjectValue::Unsigned8(0x10));
+
+    controller.init();
+
+    let od = controller.object_dictionary_mut();
+    od.write(0x1017, 0x00, ObjectValue::Unsigned16(500))
+        .unwrap();
+    od.write(0x2000, 0x00, ObjectValue::Unsigned8(0x20))
+        .unwrap();
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x82, 0x1A]));
+
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(1000)));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(0x20)));
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x81, 0x1A]));
+
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(0x10)));
+}
+
+#[test]
+fn test_can_open_controller_mandatory_objects() {
+    let mut controller = CanOpenController::with_identity(
+        0x1A,
+        Identity {
+            device_type: 0x00020192,
+            vendor_id: 0x12345678,
+            product_code: 0x1,
+            revision_number: 0x00010002,
+            serial_number: 0xCAFE,
+        },
+    );
+    controller.init();
+
+    let od = controller.object_dictionary();
+    assert_eq!(
+        od.read(0x1000, 0x00),
+        Ok(ObjectValue::Unsigned32(0x00020192))
+    );
+    assert_eq!(od.read(0x1001, 0x00), Ok(ObjectValue::Unsigned8(0)));
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(0)));
+    assert_eq!(od.read(0x1018, 0x00), Ok(ObjectValue::Unsigned8(4)));
+    assert_eq!(
+        od.read(0x1018, 0x01),
+        Ok(ObjectValue::Unsigned32(0x12345678))
+    );
+    assert_eq!(od.read(0x1018, 0x02), Ok(ObjectValue::Unsigned32(0x1)));
+    assert_eq!(
+        od.read(0x1018, 0x03),
+        Ok(ObjectValue::Unsigned32(0x00010002))
+    );
+    assert_eq!(od.read(0x1018, 0x04), Ok(ObjectValue::Unsigned32(0xCAFE)));
+
+    // The identity can be read but not written over the bus.
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x40, 0x18, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00],
+    ));
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x23, 0x18, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00],
+    ));
+    let messages = controller.fetch();
+    assert_eq!(
+        *messages[1].data(),
+        vec![0x43, 0x18, 0x10, 0x01, 0x78, 0x56, 0x34, 0x12]
+    );
+    assert_eq!(
+        *messages[2].data(),
+        vec![0x80, 0x18, 0x10, 0x01, 0x02, 0x00, 0x01, 0x06]
+    );
+}
+
+#[test]
+fn test_can_open_controller_keeps_application_objects() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x1018, 0x01, ObjectValue::Unsigned32(0x42));
+    controller.init();
+
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x1018, 0x01), Ok(ObjectValue::Unsigned32(0x42)));
+    assert_eq!(od.read(0x1018, 0x02), Ok(ObjectValue::Unsigned32(0)));
+    assert_eq!(od.read(0x1000, 0x00), Ok(ObjectValue::Unsigned32(0)));
+}
+
+fn sdo_download_u32(controller: &mut CanOpenController, index: u16, sub_index: u8, value: u32) {
+    let index = index.to_le_bytes();
+    let value = value.to_le_bytes();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![
+            0x23, index[0], index[1], sub_index, value[0], value[1], value[2], value[3],
+        ],
+    ));
+}
+
+#[test]
+fn test_can_open_controller_store_and_restore_parameters() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.set_storage(Box::new(MemoryStorage::new()));
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x00, ObjectValue::Unsigned8(0x10));
+    controller.init();
+    assert_eq!(
+        controller.object_dictionary().read(0x1010, 0x01),
+        Ok(ObjectValue::Unsigned32(1))
+    );
+
+    let od = controller.object_dictionary_mut();
+    od.write(0x1017, 0x00, ObjectValue::Unsigned16(500))
+        .unwrap();
+    od.write(0x2000, 0x00, ObjectValue::Unsigned8(0x20))
+        .unwrap();
+
+    // Save all parameters, then change them again.
+    controller.fetch();
+    sdo_download_u32(&mut controller, 0x1010, 0x01, 0x6576_6173);
+    assert_eq!(
+        *controller.fetch().pop().unwrap().data(),
+        vec![0x60, 0x10, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        controller.object_dictionary().read(0x1010, 0x01),
+        Ok(ObjectValue::Unsigned32(1))
+    );
+    let od = controller.object_dictionary_mut();
+    od.write(0x1017, 0x00, ObjectValue::Unsigned16(100))
+        .unwrap();
+    od.write(0x2000, 0x00, ObjectValue::Unsigned8(0x30))
+        .unwrap();
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x81, 0x1A]));
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(500)));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(0x20)));
+
+    // Restoring the application defaults only affects that area.
+    sdo_download_u32(&mut controller, 0x1011, 0x03, 0x6461_6F6C);
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x81, 0x1A]));
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(500)));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(0x10)));
+}
+
+#[test]
+fn test_can_open_controller_store_parameters_wrong_signature() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.set_storage(Box::new(MemoryStorage::new()));
+    controller.init();
+
+    controller
+        .object_dictionary_mut()
+        .write(0x1017, 0x00, ObjectValue::Unsigned16(500))
+        .unwrap();
+    controller.fetch();
+    sdo_download_u32(&mut controller, 0x1010, 0x02, 0x1234_5678);
+    assert_eq!(
+        *controller.fetch().pop().unwrap().data(),
+        vec![0x80, 0x10, 0x10, 0x02, 0x20, 0x00, 0x00, 0x08]
+    );
+    assert_eq!(
+        controller.object_dictionary().read(0x1010, 0x02),
+        Ok(ObjectValue::Unsigned32(1))
+    );
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x82, 0x1A]));
+    assert_eq!(
+        controller.object_dictionary().read(0x1017, 0x00),
+        Ok(ObjectValue::Unsigned16(0))
+    );
+}
+
+#[test]
+fn test_can_open_controller_store_parameters_without_storage() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.init();
+    assert_eq!(
+        controller.object_dictionary().read(0x1010, 0x01),
+        Ok(ObjectValue::Unsigned32(0))
+    );
+
+    controller.fetch();
+    sdo_download_u32(&mut controller, 0x1010, 0x01, 0x6576_6173);
+    assert_eq!(
+        *controller.fetch().pop().unwrap().data(),
+        vec![0x80, 0x10, 0x10, 0x01, 0x20, 0x00, 0x00, 0x08]
+    );
+}
+
+struct FailingStorage;
+
+impl Storage for FailingStorage {
+    fn save(&mut self, _area: ParameterArea, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "write failed"))
+    }
+
+    fn load(&mut self, _area: ParameterArea) -> io::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn clear(&mut self, _area: ParameterArea) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "erase failed"))
+    }
+}
+
+#[test]
+fn test_can_open_controller_store_parameters_hardware_error() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.set_storage(Box::new(FailingStorage));
+    controller.init();
+
+    controller.fetch();
+    sdo_download_u32(&mut controller, 0x1010, 0x02, 0x6576_6173);
+    sdo_download_u32(&mut controller, 0x1011, 0x03, 0x6461_6F6C);
+    let messages = controller.fetch();
+    assert_eq!(
+        *messages[0].data(),
+        vec![0x80, 0x10, 0x10, 0x02, 0x00, 0x00, 0x06, 0x06]
+    );
+    assert_eq!(
+        *messages[1].data(),
+        vec![0x80, 0x11, 0x10, 0x03, 0x00, 0x00, 0x06, 0x06]
+    );
+}
+
+#[test]
+fn test_can_open_controller_keeps_application_store_objects() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.set_storage(Box::new(MemoryStorage::new()));
+    controller
+        .object_dictionary_mut()
+        .add(0x1010, 0x01, ObjectValue::Unsigned32(0x2));
+    controller.init();
+
+    let od = controller.object_dictionary();
+    assert_eq!(od.read(0x1010, 0x01), Ok(ObjectValue::Unsigned32(0x2)));
+    assert!(od.read(0x1010, 0x02).is_err());
+    assert_eq!(od.read(0x1011, 0x01), Ok(ObjectValue::Unsigned32(1)));
+}
+
+#[test]
+fn test_can_open_controller_rpdo() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x00, ObjectValue::Unsigned16(0));
+    controller.init();
+    let od = controller.object_dictionary_mut();
+    assert_eq!(od.read(0x1400, 0x01), Ok(ObjectValue::Unsigned32(0x21A)));
+    assert_eq!(od.read(0x1403, 0x01), Ok(ObjectValue::Unsigned32(0x51A)));
+    od.write(0x1600, 0x01, ObjectValue::Unsigned32(0x2000_0010))
+        .unwrap();
+    od.write(0x1600, 0x00, ObjectValue::Unsigned8(1)).unwrap();
+
+    // RPDOs are only processed in the operational state.
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::Pdo1Rx,
+        vec![0x34, 0x12],
+    ));
+    assert_eq!(
+        controller.object_dictionary().read(0x2000, 0x00),
+        Ok(ObjectValue::Unsigned16(0))
+    );
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x01, 0x1A]));
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::Pdo1Rx,
+        vec![0x34, 0x12],
+    ));
+    assert_eq!(
+        controller.object_dictionary().read(0x2000, 0x00),
+        Ok(ObjectValue::Unsigned16(0x1234))
+    );
+
+    // Synchronous RPDOs take effect on the next SYNC.
+    controller
+        .object_dictionary_mut()
+        .write(0x1400, 0x02, ObjectValue::Unsigned8(0))
+        .unwrap();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::Pdo1Rx,
+        vec![0x78, 0x56],
+    ));
+    assert_eq!(
+        controller.object_dictionary().read(0x2000, 0x00),
+        Ok(ObjectValue::Unsigned16(0x1234))
+    );
+    controller.process(CanMessage::from_cob(Cob::Sync, vec![]));
+    assert_eq!(
+        controller.object_dictionary().read(0x2000, 0x00),
+        Ok(ObjectValue::Unsigned16(0x5678))
+    );
+}
+
+#[test]
+fn test_can_open_controller_tpdo() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x00, ObjectValue::Unsigned16(0x1234));
+    controller.init();
+    controller.fetch();
+    let od = controller.object_dictionary_mut();
+    assert_eq!(od.read(0x1800, 0x01), Ok(ObjectValue::Unsigned32(0x19A)));
+    assert_eq!(od.read(0x1803, 0x01), Ok(ObjectValue::Unsigned32(0x49A)));
+    od.write(0x1A00, 0x01, ObjectValue::Unsigned32(0x2000_0010))
+        .unwrap();
+    od.write(0x1A00, 0x00, ObjectValue::Unsigned8(1)).unwrap();
+    od.write(0x1A01, 0x01, ObjectValue::Unsigned32(0x2000_0010))
+        .unwrap();
+    od.write(0x1A01, 0x00, ObjectValue::Unsigned8(1)).unwrap();
+    od.write(0x1801, 0x02, ObjectValue::Unsigned8(1)).unwrap();
+
+    // TPDOs are only transmitted in the operational state.
+    controller.process(CanMessage::from_cob(Cob::Sync, vec![]));
+    controller.update(Duration::from_millis(1));
+    assert!(controller.fetch().is_empty());
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x01, 0x1A]));
+    controller.update(Duration::from_millis(1));
+    assert!(controller.fetch().is_empty());
+
+    controller.process(CanMessage::from_cob(Cob::Sync, vec![]));
+    let msgs = controller.fetch();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].can_id(), 0x29A);
+    assert_eq!(*msgs[0].data(), vec![0x34, 0x12]);
+
+    controller
+        .object_dictionary_mut()
+        .write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+        .unwrap();
+    controller.update(Duration::from_millis(1));
+    let msgs = controller.fetch();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].can_id(), 0x19A);
+    assert_eq!(*msgs[0].data(), vec![0x78, 0x56]);
+}
+
+fn sdo_download_u8(controller: &mut CanOpenController, index: u16, sub_index: u8, value: u8) {
+    let index = index.to_le_bytes();
+    controller.process(CanMessage::from_node_id(
+        0x1A,
+        Cob::SdoRx,
+        vec![0x2F, index[0], index[1], sub_index, value, 0, 0, 0],
+    ));
+}
+
+fn sdo_response(controller: &mut CanOpenController) -> Vec<u8> {
+    controller.fetch().pop().unwrap().data().clone()
+}
+
+#[test]
+fn test_can_open_controller_pdo_remapping() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller.object_dictionary_mut().add_with_attributes(
+        0x2000,
+        0x00,
+        ObjectValue::Unsigned16(0x1234),
+        ObjectAttributes {
+            pdo_mappable: true,
+            ..Default::default()
+        },
+    );
+    controller.init();
+    controller.fetch();
+
+    // The mapping can't change while the PDO is valid.
+    sdo_download_u8(&mut controller, 0x1A00, 0x00, 0);
+    assert_eq!(
+        sdo_response(&mut controller),
+        vec![0x80, 0x00, 0x1A, 0x00, 0x22, 0x00, 0x00, 0x08]
+    );
+
+    sdo_download_u32(&mut controller, 0x1800, 0x01, 0x8000_019A);
+    sdo_download_u8(&mut controller, 0x1A00, 0x00, 0);
+    sdo_download_u32(&mut controller, 0x1A00, 0x01, 0x1017_0010);
+    assert_eq!(
+        sdo_response(&mut controller),
+        vec![0x80, 0x00, 0x1A, 0x01, 0x41, 0x00, 0x04, 0x06]
+    );
+    sdo_download_u32(&mut controller, 0x1A00, 0x01, 0x2000_0010);
+    sdo_download_u8(&mut controller, 0x1A00, 0x00, 1);
+    sdo_download_u32(&mut controller, 0x1800, 0x01, 0x0000_019A);
+    assert_eq!(
+        sdo_response(&mut controller),
+        vec![0x60, 0x00, 0x18, 0x01, 0x00, 0x00, 0x00, 0x00]
+    );
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x01, 0x1A]));
+    controller
+        .object_dictionary_mut()
+        .write(0x2000, 0x00, ObjectValue::Unsigned16(0x5678))
+        .unwrap();
+    controller.update(Duration::from_millis(1));
+    let msgs = controller.fetch();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].can_id(), 0x19A);
+    assert_eq!(*msgs[0].data(), vec![0x78, 0x56]);
+}
+
+#[test]
+fn test_can_open_controller_tpdo_remote_request() {
+    let mut controller = CanOpenController::new(0x1A);
+    controller
+        .object_dictionary_mut()
+        .add(0x2000, 0x00, ObjectValue::Unsigned16(0x1234));
+    controller.init();
+    let od = controller.object_dictionary_mut();
+    od.write(0x1A00, 0x01, ObjectValue::Unsigned32(0x2000_0010))
+        .unwrap();
+    od.write(0x1A00, 0x00, ObjectValue::Unsigned8(1)).unwrap();
+    od.write(0x1800, 0x02, ObjectValue::Unsigned8(253)).unwrap();
+    controller.fetch();
+
+    controller.process(CanMessage::remote_request(0x19A));
+    assert!(controller.fetch().is_empty());
+
+    controller.process(CanMessage::from_cob(Cob::Nmt, vec![0x01, 0x1A]));
+    controller.process(CanMessage::remote_request(0x19A));
+    let msgs = controller.fetch();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].can_id(), 0x19A);
+    assert!(!msgs[0].is_rtr());
+    assert_eq!(*msgs[0].data(), vec![0x34, 0x12]);
+
+    // Remote requests are ignored when the COB-ID doesn't allow them.
+    controller
+        .object_dictionary_mut()
+        .write(0x1800, 0x01, ObjectValue::Unsigned32(0x4000_019A))
+        .unwrap();
+    controller.process(CanMessage::remote_request(0x19A));
+    assert!(controller.fetch().is_empty());
+}
@@ -0,0 +1,67 @@
+// Generated by canopen_rs::codegen, do not edit.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use canopen_rs::od::{ObjectAccess, ObjectDictionaryError, ObjectSubscriber, ObjectValue};
+
+pub struct EmptyOd {
+    subscribers: Vec<EmptyOdSubscription>,
+}
+
+type EmptyOdSubscription = (u16, u8, Rc<RefCell<dyn ObjectSubscriber>>);
+
+#[allow(dead_code)]
+impl EmptyOd {
+    pub fn new() -> EmptyOd {
+        EmptyOd {
+            subscribers: Vec::new(),
+        }
+    }
+
+    fn notify_subscribers(&self, index: u16, sub_index: u8) -> Result<(), ObjectDictionaryError> {
+        let value = self.read(index, sub_index)?;
+        for (i, s, subscriber) in self.subscribers.iter() {
+            if *i == index && *s == sub_index {
+                subscriber.borrow_mut().object_updated(index, sub_index, &value);
+            }
+        }
+        Ok(())
+    }
+
+    fn missing_entry_error(_index: u16) -> ObjectDictionaryError {
+        ObjectDictionaryError::ObjectDoesNotExist
+    }
+}
+
+impl Default for EmptyOd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjectAccess for EmptyOd {
+    fn read(&self, index: u16, _sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        Err(Self::missing_entry_error(index))
+    }
+
+    fn write(
+        &mut self,
+        index: u16,
+        _sub_index: u8,
+        _value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        Err(Self::missing_entry_error(index))
+    }
+
+    fn subscribe(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.read(index, sub_index)?;
+        self.subscribers.push((index, sub_index, subscriber));
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+// Generated by canopen_rs::codegen, do not edit.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use canopen_rs::od::{ObjectAccess, ObjectDictionaryError, ObjectSubscriber, ObjectValue};
+
+pub struct SampleOd {
+    device_type: u32,
+    producer_heartbeat_time: u16,
+    identity_object_highest_sub_index_supported: u8,
+    identity_object_vendor_id: u32,
+    object_1200_sub0: u8,
+    object_1200_sub1: u32,
+    setpoint: i16,
+    device_name: String,
+    gain: f64,
+    alarm_time: canopen_rs::od::TimeOfDay,
+    subscribers: Vec<SampleOdSubscription>,
+}
+
+type SampleOdSubscription = (u16, u8, Rc<RefCell<dyn ObjectSubscriber>>);
+
+#[allow(dead_code)]
+impl SampleOd {
+    pub fn new() -> SampleOd {
+        SampleOd {
+            device_type: 0x20192,
+            producer_heartbeat_time: 0x0,
+            identity_object_highest_sub_index_supported: 0x1,
+            identity_object_vendor_id: 0x12345678,
+            object_1200_sub0: 0x1,
+            object_1200_sub1: 0x605,
+            setpoint: -100,
+            device_name: String::from("My device"),
+            gain: f64::from_bits(0x3FE0000000000000),
+            alarm_time: canopen_rs::od::TimeOfDay { milliseconds: 0, days: 0 },
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn device_type(&self) -> u32 {
+        self.device_type
+    }
+
+    pub fn producer_heartbeat_time(&self) -> u16 {
+        self.producer_heartbeat_time
+    }
+
+    pub fn set_producer_heartbeat_time(&mut self, value: u16) -> Result<(), ObjectDictionaryError> {
+        self.write(0x1017, 0x00, ObjectValue::Unsigned16(value))
+    }
+
+    pub fn identity_object_highest_sub_index_supported(&self) -> u8 {
+        self.identity_object_highest_sub_index_supported
+    }
+
+    pub fn identity_object_vendor_id(&self) -> u32 {
+        self.identity_object_vendor_id
+    }
+
+    pub fn object_1200_sub0(&self) -> u8 {
+        self.object_1200_sub0
+    }
+
+    pub fn object_1200_sub1(&self) -> u32 {
+        self.object_1200_sub1
+    }
+
+    pub fn setpoint(&self) -> i16 {
+        self.setpoint
+    }
+
+    pub fn set_setpoint(&mut self, value: i16) -> Result<(), ObjectDictionaryError> {
+        self.write(0x2000, 0x00, ObjectValue::Integer16(value))
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    pub fn set_gain(&mut self, value: f64) -> Result<(), ObjectDictionaryError> {
+        self.write(0x2002, 0x00, ObjectValue::Real64(value))
+    }
+
+    pub fn alarm_time(&self) -> canopen_rs::od::TimeOfDay {
+        self.alarm_time
+    }
+
+    pub fn set_alarm_time(&mut self, value: canopen_rs::od::TimeOfDay) -> Result<(), ObjectDictionaryError> {
+        self.write(0x2003, 0x00, ObjectValue::TimeOfDay(value))
+    }
+
+    fn notify_subscribers(&self, index: u16, sub_index: u8) -> Result<(), ObjectDictionaryError> {
+        let value = self.read(index, sub_index)?;
+        for (i, s, subscriber) in self.subscribers.iter() {
+            if *i == index && *s == sub_index {
+                subscriber.borrow_mut().object_updated(index, sub_index, &value);
+            }
+        }
+        Ok(())
+    }
+
+    fn missing_entry_error(index: u16) -> ObjectDictionaryError {
+        match index {
+            0x1000 | 0x1017 | 0x1018 | 0x1200 | 0x2000 | 0x2001 | 0x2002 | 0x2003 => ObjectDictionaryError::SubIndexDoesNotExist,
+            _ => ObjectDictionaryError::ObjectDoesNotExist,
+        }
+    }
+}
+
+impl Default for SampleOd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjectAccess for SampleOd {
+    fn read(&self, index: u16, sub_index: u8) -> Result<ObjectValue, ObjectDictionaryError> {
+        match (index, sub_index) {
+            (0x1000, 0x00) => Ok(ObjectValue::Unsigned32(self.device_type)),
+            (0x1017, 0x00) => Ok(ObjectValue::Unsigned16(self.producer_heartbeat_time)),
+            (0x1018, 0x00) => Ok(ObjectValue::Unsigned8(self.identity_object_highest_sub_index_supported)),
+            (0x1018, 0x01) => Ok(ObjectValue::Unsigned32(self.identity_object_vendor_id)),
+            (0x1200, 0x00) => Ok(ObjectValue::Unsigned8(self.object_1200_sub0)),
+            (0x1200, 0x01) => Ok(ObjectValue::Unsigned32(self.object_1200_sub1)),
+            (0x2000, 0x00) => Ok(ObjectValue::Integer16(self.setpoint)),
+            (0x2001, 0x00) => Ok(ObjectValue::VisibleString(self.device_name.clone())),
+            (0x2002, 0x00) => Ok(ObjectValue::Real64(self.gain)),
+            (0x2003, 0x00) => Ok(ObjectValue::TimeOfDay(self.alarm_time)),
+            _ => Err(Self::missing_entry_error(index)),
+        }
+    }
+
+    fn write(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        value: ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        match (index, sub_index, value) {
+            (0x1000, 0x00, ObjectValue::Unsigned32(value)) => self.device_type = value,
+            (0x1017, 0x00, ObjectValue::Unsigned16(value)) => self.producer_heartbeat_time = value,
+            (0x1018, 0x00, ObjectValue::Unsigned8(value)) => self.identity_object_highest_sub_index_supported = value,
+            (0x1018, 0x01, ObjectValue::Unsigned32(value)) => self.identity_object_vendor_id = value,
+            (0x1200, 0x00, ObjectValue::Unsigned8(value)) => self.object_1200_sub0 = value,
+            (0x1200, 0x01, ObjectValue::Unsigned32(value)) => self.object_1200_sub1 = value,
+            (0x2000, 0x00, ObjectValue::Integer16(value)) => {
+                if value < -1000 {
+                    return Err(ObjectDictionaryError::ValueTooLow);
+                }
+                if value > 1000 {
+                    return Err(ObjectDictionaryError::ValueTooHigh);
+                }
+                self.setpoint = value;
+            }
+            (0x2001, 0x00, ObjectValue::VisibleString(value)) => self.device_name = value,
+            (0x2002, 0x00, ObjectValue::Real64(value)) => self.gain = value,
+            (0x2003, 0x00, ObjectValue::TimeOfDay(value)) => self.alarm_time = value,
+            (index, sub_index, _) => {
+                return Err(match self.read(index, sub_index) {
+                    Ok(_) => ObjectDictionaryError::DataTypeMismatch,
+                    Err(error) => error,
+                })
+            }
+        }
+        self.notify_subscribers(index, sub_index)
+    }
+
+    fn subscribe(
+        &mut self,
+        index: u16,
+        sub_index: u8,
+        subscriber: Rc<RefCell<dyn ObjectSubscriber>>,
+    ) -> Result<(), ObjectDictionaryError> {
+        self.read(index, sub_index)?;
+        self.subscribers.push((index, sub_index, subscriber));
+        Ok(())
+    }
+}
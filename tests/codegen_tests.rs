@@ -0,0 +1,133 @@
+extern crate canopen_rs;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use canopen_rs::codegen;
+use canopen_rs::od::{
+    ObjectAccess, ObjectDictionaryError, ObjectSubscriber, ObjectValue, TimeOfDay,
+};
+
+mod generated {
+    include!("codegen/sample_od.rs");
+}
+
+mod generated_empty {
+    include!("codegen/empty_od.rs");
+}
+
+use generated::SampleOd;
+use generated_empty::EmptyOd;
+
+const EDS: &str = include_str!("codegen/sample.eds");
+
+struct LastValue {
+    value: Option<ObjectValue>,
+}
+
+impl ObjectSubscriber for LastValue {
+    fn object_updated(&mut self, _index: u16, _sub_index: u8, value: &ObjectValue) {
+        self.value = Some(value.clone());
+    }
+}
+
+#[test]
+fn test_generate_matches_golden_file() {
+    let source = codegen::generate(EDS, 5, "SampleOd").unwrap();
+    assert_eq!(source, include_str!("codegen/sample_od.rs"));
+}
+
+#[test]
+fn test_generate_empty_eds() {
+    let source = codegen::generate("", 5, "EmptyOd").unwrap();
+    assert_eq!(source, include_str!("codegen/empty_od.rs"));
+
+    let mut od = EmptyOd::new();
+    assert_eq!(
+        od.read(0x1000, 0),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+    assert_eq!(
+        od.write(0x1000, 0, ObjectValue::Unsigned32(0)),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+}
+
+#[test]
+fn test_generated_accessors() {
+    let mut od = SampleOd::new();
+    assert_eq!(od.device_type(), 0x00020192);
+    assert_eq!(od.producer_heartbeat_time(), 0);
+    assert_eq!(od.identity_object_vendor_id(), 0x12345678);
+    assert_eq!(od.object_1200_sub1(), 0x605);
+    assert_eq!(od.device_name(), "My device");
+    assert_eq!(od.gain(), 0.5);
+
+    od.set_producer_heartbeat_time(1000).unwrap();
+    assert_eq!(od.producer_heartbeat_time(), 1000);
+    assert_eq!(
+        od.set_setpoint(1001),
+        Err(ObjectDictionaryError::ValueTooHigh)
+    );
+    assert_eq!(
+        od.set_setpoint(-1001),
+        Err(ObjectDictionaryError::ValueTooLow)
+    );
+    assert_eq!(od.setpoint(), -100);
+
+    // Limits on types without an order are not checked.
+    let time = TimeOfDay {
+        milliseconds: 1,
+        days: 0x2000,
+    };
+    od.set_alarm_time(time).unwrap();
+    assert_eq!(od.alarm_time(), time);
+}
+
+#[test]
+fn test_generated_object_access() {
+    let mut od = SampleOd::default();
+    assert_eq!(od.read(0x1018, 0), Ok(ObjectValue::Unsigned8(1)));
+    assert_eq!(
+        od.read(0x2001, 0),
+        Ok(ObjectValue::VisibleString(String::from("My device")))
+    );
+    assert_eq!(
+        od.read(0x1018, 2),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+    assert_eq!(
+        od.read(0x3000, 0),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+
+    assert_eq!(od.write(0x2000, 0, ObjectValue::Integer16(5)), Ok(()));
+    assert_eq!(od.setpoint(), 5);
+    assert_eq!(
+        od.write(0x2000, 0, ObjectValue::Unsigned16(5)),
+        Err(ObjectDictionaryError::DataTypeMismatch)
+    );
+    assert_eq!(
+        od.write(0x2000, 1, ObjectValue::Integer16(5)),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+}
+
+#[test]
+fn test_generated_subscribe() {
+    let mut od = SampleOd::new();
+    let subscriber = Rc::new(RefCell::new(LastValue { value: None }));
+    od.subscribe(0x1017, 0, subscriber.clone()).unwrap();
+    assert_eq!(
+        od.subscribe(0x1017, 1, subscriber.clone()),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+
+    od.set_gain(1.0).unwrap();
+    assert_eq!(subscriber.borrow().value, None);
+    od.set_producer_heartbeat_time(250).unwrap();
+    assert_eq!(
+        subscriber.borrow().value,
+        Some(ObjectValue::Unsigned16(250))
+    );
+}
@@ -0,0 +1,157 @@
+extern crate canopen_rs;
+
+use canopen_rs::concise_dcf::{self, ConciseDcfEntry, ConciseDcfError};
+use canopen_rs::od::{
+    AccessType, ObjectAttributes, ObjectDictionary, ObjectDictionaryError, ObjectValue,
+};
+
+fn create_od() -> ObjectDictionary {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1017, 0, ObjectValue::Unsigned16(0));
+    od.add(0x1800, 1, ObjectValue::Unsigned32(0x180));
+    od.add(0x2000, 0, ObjectValue::VisibleString(String::new()));
+    od
+}
+
+const BLOB: [u8; 25] = [
+    0x02, 0x00, 0x00, 0x00, // two entries
+    0x17, 0x10, 0x00, 0x02, 0x00, 0x00, 0x00, 0xE8, 0x03, // 0x1017 = 1000
+    0x00, 0x20, 0x00, 0x03, 0x00, 0x00, 0x00, b'a', b'b', b'c', // 0x2000 = "abc"
+    0x00, 0x00,
+];
+
+#[test]
+fn test_parse_and_generate() {
+    let entries = concise_dcf::parse(&BLOB[..23]).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            ConciseDcfEntry {
+                index: 0x1017,
+                sub_index: 0,
+                data: vec![0xE8, 0x03],
+            },
+            ConciseDcfEntry {
+                index: 0x2000,
+                sub_index: 0,
+                data: b"abc".to_vec(),
+            },
+        ]
+    );
+    assert_eq!(concise_dcf::generate(&entries), BLOB[..23].to_vec());
+}
+
+#[test]
+fn test_parse_malformed() {
+    assert_eq!(
+        concise_dcf::parse(&BLOB[..2]),
+        Err(ConciseDcfError::Truncated { entry: 0 })
+    );
+    assert_eq!(
+        concise_dcf::parse(&BLOB[..22]),
+        Err(ConciseDcfError::Truncated { entry: 2 })
+    );
+    assert_eq!(
+        concise_dcf::parse(&BLOB),
+        Err(ConciseDcfError::TrailingData)
+    );
+}
+
+#[test]
+fn test_apply() {
+    let mut od = create_od();
+    concise_dcf::apply(&mut od, &BLOB[..23]).unwrap();
+    assert_eq!(od.read(0x1017, 0), Ok(ObjectValue::Unsigned16(1000)));
+    assert_eq!(
+        od.read(0x2000, 0),
+        Ok(ObjectValue::VisibleString(String::from("abc")))
+    );
+}
+
+#[test]
+fn test_apply_reports_failing_entry() {
+    let mut od = create_od();
+    let blob = concise_dcf::generate(&[
+        ConciseDcfEntry {
+            index: 0x1017,
+            sub_index: 0,
+            data: vec![0x64, 0x00],
+        },
+        ConciseDcfEntry {
+            index: 0x1800,
+            sub_index: 1,
+            data: vec![0x80, 0x01],
+        },
+    ]);
+
+    assert_eq!(
+        concise_dcf::apply(&mut od, &blob),
+        Err(ConciseDcfError::EntryFailed {
+            index: 0x1800,
+            sub_index: 1,
+            error: ObjectDictionaryError::LengthTooLow,
+        })
+    );
+    assert_eq!(od.read(0x1017, 0), Ok(ObjectValue::Unsigned16(100)));
+
+    let blob = concise_dcf::generate(&[ConciseDcfEntry {
+        index: 0x1801,
+        sub_index: 1,
+        data: vec![0x80, 0x01, 0x00, 0x00],
+    }]);
+    let error = concise_dcf::apply(&mut od, &blob).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "entry 0x1801sub1 failed: object does not exist (abort code 0x06020000)"
+    );
+}
+
+#[test]
+fn test_apply_read_only_entry() {
+    let mut od = create_od();
+    od.add_with_attributes(
+        0x1018,
+        1,
+        ObjectValue::Unsigned32(0),
+        ObjectAttributes {
+            access_type: AccessType::ReadOnly,
+            ..ObjectAttributes::default()
+        },
+    );
+    let blob = concise_dcf::generate(&[ConciseDcfEntry {
+        index: 0x1018,
+        sub_index: 1,
+        data: vec![0x78, 0x56, 0x34, 0x12],
+    }]);
+
+    assert_eq!(
+        concise_dcf::apply(&mut od, &blob),
+        Err(ConciseDcfError::EntryFailed {
+            index: 0x1018,
+            sub_index: 1,
+            error: ObjectDictionaryError::ReadOnly,
+        })
+    );
+    assert_eq!(od.read(0x1018, 1), Ok(ObjectValue::Unsigned32(0)));
+
+    concise_dcf::apply_trusted(&mut od, &blob).unwrap();
+    assert_eq!(od.read(0x1018, 1), Ok(ObjectValue::Unsigned32(0x1234_5678)));
+}
+
+#[test]
+fn test_entries_from_dictionary() {
+    let od = create_od();
+    let entries = concise_dcf::entries_from(&od, &[(0x1800, 1)]).unwrap();
+    assert_eq!(
+        entries,
+        vec![ConciseDcfEntry {
+            index: 0x1800,
+            sub_index: 1,
+            data: vec![0x80, 0x01, 0x00, 0x00],
+        }]
+    );
+    assert_eq!(
+        concise_dcf::entries_from(&od, &[(0x1800, 2)]),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+}
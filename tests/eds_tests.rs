@@ -0,0 +1,301 @@
+extern crate canopen_rs;
+
+use canopen_rs::eds;
+use canopen_rs::od::{AccessType, ObjectDictionaryError, ObjectType, ObjectValue};
+
+const EDS: &str = "\
+[FileInfo]
+FileName=test.eds
+; Comments are ignored
+
+[MandatoryObjects]
+SupportedObjects=2
+1=0x1000
+2=0x1018
+
+[1000]
+ParameterName=Device type
+ObjectType=0x7
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x00020192
+PDOMapping=0
+
+[1018]
+ParameterName=Identity object
+ObjectType=0x9
+SubNumber=2
+
+[1018sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=const
+DefaultValue=1
+
+[1018sub1]
+ParameterName=Vendor-ID
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x12345678
+
+[1200sub1]
+ParameterName=COB-ID client to server
+DataType=0x0007
+AccessType=ro
+DefaultValue=$NODEID+0x600
+
+[2000]
+ParameterName=Setpoint
+DataType=0x0003
+AccessType=rww
+DefaultValue=-100
+LowLimit=-1000
+HighLimit=1000
+PDOMapping=1
+
+[2001]
+ParameterName=Device name
+DataType=0x0009
+AccessType=ro
+DefaultValue=My device
+
+[2002]
+ParameterName=Counters
+ObjectType=0x8
+DataType=0x001B
+AccessType=rwr
+DefaultValue=010
+CompactSubObj=3
+";
+
+#[test]
+fn test_parse_values() {
+    let od = eds::parse(EDS, 5).unwrap();
+    assert_eq!(od.read(0x1000, 0), Ok(ObjectValue::Unsigned32(0x00020192)));
+    assert_eq!(od.read(0x1018, 0), Ok(ObjectValue::Unsigned8(1)));
+    assert_eq!(od.read(0x1018, 1), Ok(ObjectValue::Unsigned32(0x12345678)));
+    assert_eq!(od.read(0x1200, 1), Ok(ObjectValue::Unsigned32(0x605)));
+    assert_eq!(od.read(0x2000, 0), Ok(ObjectValue::Integer16(-100)));
+    assert_eq!(
+        od.read(0x2001, 0),
+        Ok(ObjectValue::VisibleString(String::from("My device")))
+    );
+    assert_eq!(
+        od.read(0x1018, 2),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+}
+
+#[test]
+fn test_parse_signed_hex_values() {
+    let od = eds::parse(
+        "[2000]\nDataType=0x0003\nAccessType=rw\nDefaultValue=0xFFFF\n\n\
+         [2001]\nDataType=0x0002\nAccessType=rw\nDefaultValue=0x7F\n",
+        0,
+    )
+    .unwrap();
+    assert_eq!(od.read(0x2000, 0), Ok(ObjectValue::Integer16(-1)));
+    assert_eq!(od.read(0x2001, 0), Ok(ObjectValue::Integer8(127)));
+
+    let error = parse_error("[2000]\nDataType=0x0003\nAccessType=rw\nDefaultValue=0x10000\n");
+    assert_eq!(
+        error.to_string(),
+        "line 4: value '0x10000' out of range for Integer16"
+    );
+}
+
+#[test]
+fn test_parse_attributes() {
+    let mut od = eds::parse(EDS, 5).unwrap();
+
+    let attributes = od.attributes(0x1000, 0).unwrap();
+    assert_eq!(attributes.access_type, AccessType::ReadOnly);
+    assert_eq!(attributes.name, Some(String::from("Device type")));
+    assert!(!attributes.pdo_mappable);
+    assert_eq!(
+        od.attributes(0x1018, 0).unwrap().access_type,
+        AccessType::Const
+    );
+
+    let attributes = od.attributes(0x2000, 0).unwrap();
+    assert_eq!(attributes.access_type, AccessType::ReadWriteWrite);
+    assert!(attributes.pdo_mappable);
+    assert_eq!(attributes.low_limit, Some(ObjectValue::Integer16(-1000)));
+    assert_eq!(attributes.high_limit, Some(ObjectValue::Integer16(1000)));
+    assert_eq!(
+        od.write(0x2000, 0, ObjectValue::Integer16(1001)),
+        Err(ObjectDictionaryError::ValueTooHigh)
+    );
+}
+
+#[test]
+fn test_parse_compact_array() {
+    let od = eds::parse(EDS, 5).unwrap();
+    assert_eq!(od.read(0x2002, 0), Ok(ObjectValue::Unsigned8(3)));
+    assert_eq!(od.object_type(0x2002), Ok(ObjectType::Array));
+    assert_eq!(od.object_name(0x2002), Ok(Some("Counters")));
+    for sub_index in 1..=3 {
+        assert_eq!(od.read(0x2002, sub_index), Ok(ObjectValue::Unsigned64(8)));
+        let attributes = od.attributes(0x2002, sub_index).unwrap();
+        assert_eq!(attributes.access_type, AccessType::ReadWriteRead);
+        assert_eq!(attributes.name, Some(format!("Counters{}", sub_index)));
+    }
+    assert_eq!(
+        od.read(0x2002, 4),
+        Err(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+}
+
+#[test]
+fn test_parse_missing_default_value() {
+    let od = eds::parse(
+        "[2000]\nDataType=0x0004\nAccessType=rw\n[2001]\nDataType=0x0009\nAccessType=rw\n",
+        1,
+    )
+    .unwrap();
+    assert_eq!(od.read(0x2000, 0), Ok(ObjectValue::Integer32(0)));
+    assert_eq!(
+        od.read(0x2001, 0),
+        Ok(ObjectValue::VisibleString(String::new()))
+    );
+}
+
+fn parse_error(input: &str) -> eds::EdsError {
+    match eds::parse(input, 1) {
+        Err(error) => error,
+        Ok(_) => panic!("expected a parse error"),
+    }
+}
+
+#[test]
+fn test_parse_syntax_error() {
+    let error = parse_error("[1000]\nDataType=0x0007\nAccessType\n");
+    assert_eq!(error.line, 3);
+
+    let error = parse_error("[1000\n");
+    assert_eq!(error.line, 1);
+
+    let error = parse_error("DataType=0x0007\n");
+    assert_eq!(error.line, 1);
+}
+
+#[test]
+fn test_parse_type_errors() {
+    let error = parse_error("[1000]\nDataType=0x0005\nAccessType=ro\nDefaultValue=256\n");
+    assert_eq!(error.line, 4);
+    assert_eq!(
+        error.to_string(),
+        "line 4: value '256' out of range for Unsigned8"
+    );
+
+    let error = parse_error("[1000]\nDataType=0x0042\nAccessType=ro\n");
+    assert_eq!(error.line, 2);
+
+    let error = parse_error("\n[1000]\nAccessType=ro\n");
+    assert_eq!(error.line, 2);
+
+    let error = parse_error("[1000]\nDataType=0x0007\nAccessType=rx\n");
+    assert_eq!(error.line, 3);
+
+    let error = parse_error("[1000]\nDataType=0x0008\nAccessType=ro\nDefaultValue=abc\n");
+    assert_eq!(error.line, 4);
+}
+
+#[test]
+fn test_dcf_round_trip() {
+    let mut od = eds::parse(EDS, 5).unwrap();
+    od.write(0x2000, 0, ObjectValue::Integer16(42)).unwrap();
+    od.write(0x2002, 2, ObjectValue::Unsigned64(u64::MAX))
+        .unwrap();
+    od.add(0x2003, 0, ObjectValue::Real64(-0.1));
+    od.add(0x2004, 0, ObjectValue::Domain(vec![0xDE, 0xAD]));
+    od.add(0x2005, 0, ObjectValue::Integer56(-7));
+    od.add(0x2006, 0, ObjectValue::Boolean(true));
+
+    let dcf = eds::write_dcf(&od, 5);
+    let (node_id, parsed) = eds::parse_dcf(&dcf).unwrap();
+
+    assert_eq!(node_id, 5);
+    assert_eq!(parsed.entries(), od.entries());
+    for index in od.indices() {
+        assert_eq!(parsed.object_type(index), od.object_type(index));
+        assert_eq!(parsed.object_name(index), od.object_name(index));
+    }
+    for (index, sub_index) in od.entries() {
+        assert_eq!(parsed.read(index, sub_index), od.read(index, sub_index));
+        assert_eq!(
+            parsed.default_value(index, sub_index),
+            od.default_value(index, sub_index)
+        );
+        assert_eq!(
+            parsed.attributes(index, sub_index),
+            od.attributes(index, sub_index)
+        );
+    }
+    assert_eq!(
+        parsed.default_value(0x2000, 0),
+        Ok(&ObjectValue::Integer16(-100))
+    );
+}
+
+#[test]
+fn test_dcf_round_trip_strings() {
+    let mut od = eds::parse(EDS, 5).unwrap();
+    let strings = [
+        "  padded  ",
+        "\ttab",
+        "two\nlines\r\n",
+        "back\\slash\\n",
+        " ",
+        "",
+    ];
+    for (sub_index, text) in strings.iter().enumerate() {
+        od.add(
+            0x2010,
+            sub_index as u8,
+            ObjectValue::VisibleString(text.to_string()),
+        );
+    }
+
+    let dcf = eds::write_dcf(&od, 5);
+    let (_, parsed) = eds::parse_dcf(&dcf).unwrap();
+
+    for (sub_index, text) in strings.iter().enumerate() {
+        assert_eq!(
+            parsed.read(0x2010, sub_index as u8),
+            Ok(ObjectValue::VisibleString(text.to_string()))
+        );
+    }
+    assert!(dcf.contains("ParameterValue=\\s\\spadded\\s\\s\n"));
+    assert!(dcf.contains("ParameterValue=two\\nlines\\r\\n\n"));
+}
+
+#[test]
+fn test_dcf_sections() {
+    let mut od = eds::parse(EDS, 5).unwrap();
+    od.write(0x2000, 0, ObjectValue::Integer16(42)).unwrap();
+    let dcf = eds::write_dcf(&od, 0x7F);
+
+    assert!(dcf.contains("[DeviceComissioning]\nNodeID=0x7F\n"));
+    assert!(dcf.contains("[MandatoryObjects]\nSupportedObjects=2\n1=0x1000\n2=0x1018\n"));
+    assert!(dcf.contains("[1018sub1]\nParameterName=Vendor-ID\n"));
+    assert!(dcf.contains("DefaultValue=-100\n"));
+    assert!(dcf.contains("ParameterValue=42\n"));
+}
+
+#[test]
+fn test_dcf_parameter_value_errors() {
+    let error = match eds::parse_dcf("[1000]\nDataType=0x0007\nAccessType=ro\n") {
+        Err(error) => error,
+        Ok(_) => panic!("expected a parse error"),
+    };
+    assert_eq!(error.line, 1);
+
+    let dcf = "[DeviceComissioning]\nNodeID=2\n[2000]\nDataType=0x0005\nAccessType=rw\n\
+               HighLimit=10\nParameterValue=11\n";
+    let error = match eds::parse_dcf(dcf) {
+        Err(error) => error,
+        Ok(_) => panic!("expected a parse error"),
+    };
+    assert_eq!(error.line, 7);
+}
@@ -3,7 +3,11 @@ extern crate canopen_rs;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use canopen_rs::od::{ObjectDictionary, ObjectSubscriber, ObjectValue};
+use canopen_rs::od::{
+    AccessType, DataType, ObjectAttributes, ObjectDictionary, ObjectDictionaryError,
+    ObjectProvider, ObjectSubscriber, ObjectType, ObjectValidator, ObjectValue, ParameterArea,
+    TimeDifference, TimeOfDay,
+};
 
 struct MySubscriber {
     pub value: i32,
@@ -18,16 +22,44 @@ impl ObjectSubscriber for MySubscriber {
     }
 }
 
+struct Counter {
+    pub count: u32,
+}
+
+impl ObjectProvider for Counter {
+    fn provide_value(&mut self, _index: u16, _sub_index: u8) -> ObjectValue {
+        self.count += 1;
+        ObjectValue::Unsigned32(self.count)
+    }
+}
+
+struct EvenOnly;
+
+impl ObjectValidator for EvenOnly {
+    fn validate_write(
+        &mut self,
+        _od: &ObjectDictionary,
+        _index: u16,
+        _sub_index: u8,
+        value: &ObjectValue,
+    ) -> Result<(), ObjectDictionaryError> {
+        match value {
+            ObjectValue::Integer32(v) if v % 2 != 0 => Err(ObjectDictionaryError::DeviceState),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[test]
 fn test_object_dictionary_read() {
     let mut od = ObjectDictionary::new();
     od.add(0x1000, 0x00, ObjectValue::Unsigned32(0x1234));
     match od.read(0x1000, 0x00) {
-        Some(x) => match x {
-            ObjectValue::Unsigned32(y) => assert_eq!(*y, 0x1234),
+        Ok(x) => match x {
+            ObjectValue::Unsigned32(y) => assert_eq!(y, 0x1234),
             _ => assert!(false),
         },
-        None => assert!(false),
+        Err(_) => assert!(false),
     }
 }
 
@@ -35,13 +67,14 @@ fn test_object_dictionary_read() {
 fn test_object_dictionary_write_same_value_type() {
     let mut od = ObjectDictionary::new();
     od.add(0x1000, 0x00, ObjectValue::Unsigned32(0x4000));
-    od.write(0x1000, 0x00, ObjectValue::Unsigned32(0x8200));
+    od.write(0x1000, 0x00, ObjectValue::Unsigned32(0x8200))
+        .unwrap();
     match od.read(0x1000, 0x00) {
-        Some(x) => match x {
-            ObjectValue::Unsigned32(y) => assert_eq!(*y, 0x8200),
+        Ok(x) => match x {
+            ObjectValue::Unsigned32(y) => assert_eq!(y, 0x8200),
             _ => assert!(false),
         },
-        None => assert!(false),
+        Err(_) => assert!(false),
     }
 }
 
@@ -51,9 +84,487 @@ fn test_object_dictionary_subscription() {
     od.add(0x1016, 0x01, ObjectValue::Integer32(410));
 
     let subscriber = Rc::new(RefCell::new(MySubscriber { value: 0 }));
-    od.subscribe(0x1016, 0x01, subscriber.clone());
+    od.subscribe(0x1016, 0x01, subscriber.clone()).unwrap();
 
-    od.write(0x1016, 0x01, ObjectValue::Integer32(360));
+    od.write(0x1016, 0x01, ObjectValue::Integer32(360)).unwrap();
 
     assert_eq!(subscriber.borrow().value, 360);
 }
+
+#[test]
+fn test_object_dictionary_subscribe_range() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x00, ObjectValue::Integer32(1));
+    od.add(0x3000, 0x00, ObjectValue::Integer32(2));
+
+    let subscriber = Rc::new(RefCell::new(MySubscriber { value: 0 }));
+    od.subscribe_range(0x2000..=0x2FFF, subscriber.clone());
+    od.add(0x2001, 0x01, ObjectValue::Integer32(3));
+
+    od.write(0x2000, 0x00, ObjectValue::Integer32(10)).unwrap();
+    assert_eq!(subscriber.borrow().value, 10);
+    od.write(0x2001, 0x01, ObjectValue::Integer32(11)).unwrap();
+    assert_eq!(subscriber.borrow().value, 11);
+    od.write(0x3000, 0x00, ObjectValue::Integer32(12)).unwrap();
+    assert_eq!(subscriber.borrow().value, 11);
+}
+
+#[test]
+fn test_object_dictionary_read_missing_object() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1018, 0x01, ObjectValue::Unsigned32(0x1234));
+    assert_eq!(
+        od.read(0x1017, 0x00).err(),
+        Some(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+    assert_eq!(
+        od.read(0x1018, 0x02).err(),
+        Some(ObjectDictionaryError::SubIndexDoesNotExist)
+    );
+}
+
+#[test]
+fn test_object_dictionary_write_missing_object() {
+    let mut od = ObjectDictionary::new();
+    assert_eq!(
+        od.write(0x1017, 0x00, ObjectValue::Unsigned16(100)),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+}
+
+#[test]
+fn test_object_dictionary_write_other_value_type() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1000, 0x00, ObjectValue::Unsigned32(0x4000));
+    assert_eq!(
+        od.write(0x1000, 0x00, ObjectValue::Unsigned16(0x8200)),
+        Err(ObjectDictionaryError::DataTypeMismatch)
+    );
+    match od.read(0x1000, 0x00) {
+        Ok(ObjectValue::Unsigned32(y)) => assert_eq!(y, 0x4000),
+        _ => panic!("unexpected value"),
+    }
+}
+
+#[test]
+fn test_object_dictionary_error_abort_codes() {
+    assert_eq!(
+        ObjectDictionaryError::ObjectDoesNotExist.abort_code(),
+        0x06020000
+    );
+    assert_eq!(
+        ObjectDictionaryError::SubIndexDoesNotExist.abort_code(),
+        0x06090011
+    );
+    assert_eq!(
+        ObjectDictionaryError::DataTypeMismatch.abort_code(),
+        0x06070010
+    );
+    assert_eq!(ObjectDictionaryError::ReadOnly.abort_code(), 0x06010002);
+    assert_eq!(ObjectDictionaryError::WriteOnly.abort_code(), 0x06010001);
+    assert_eq!(
+        ObjectDictionaryError::ValueRangeExceeded.abort_code(),
+        0x06090030
+    );
+}
+
+#[test]
+fn test_object_dictionary_default_attributes() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x00, ObjectValue::Unsigned8(0));
+
+    let attributes = od.attributes(0x2000, 0x00).unwrap();
+    assert_eq!(attributes.access_type, AccessType::ReadWrite);
+    assert!(!attributes.pdo_mappable);
+    assert_eq!(attributes.name, None);
+}
+
+#[test]
+fn test_object_dictionary_add_with_attributes() {
+    let mut od = ObjectDictionary::new();
+    od.add_with_attributes(
+        0x6000,
+        0x01,
+        ObjectValue::Unsigned8(0),
+        ObjectAttributes {
+            access_type: AccessType::ReadWriteRead,
+            pdo_mappable: true,
+            name: Some(String::from("Digital input")),
+            ..Default::default()
+        },
+    );
+
+    let attributes = od.attributes(0x6000, 0x01).unwrap();
+    assert_eq!(attributes.access_type, AccessType::ReadWriteRead);
+    assert!(attributes.pdo_mappable);
+    assert_eq!(attributes.name.as_deref(), Some("Digital input"));
+}
+
+#[test]
+fn test_object_dictionary_remote_access() {
+    let mut od = ObjectDictionary::new();
+    od.add_with_attributes(
+        0x1000,
+        0x00,
+        ObjectValue::Unsigned32(0x191),
+        ObjectAttributes {
+            access_type: AccessType::Const,
+            ..Default::default()
+        },
+    );
+    od.add_with_attributes(
+        0x2000,
+        0x00,
+        ObjectValue::Unsigned32(0),
+        ObjectAttributes {
+            access_type: AccessType::WriteOnly,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        od.remote_write(0x1000, 0x00, ObjectValue::Unsigned32(0)),
+        Err(ObjectDictionaryError::ReadOnly)
+    );
+    assert!(od.remote_read(0x1000, 0x00).is_ok());
+    assert_eq!(
+        od.remote_read(0x2000, 0x00).err(),
+        Some(ObjectDictionaryError::WriteOnly)
+    );
+    assert!(od
+        .remote_write(0x2000, 0x00, ObjectValue::Unsigned32(1))
+        .is_ok());
+
+    // The application itself is not restricted by the access type.
+    assert!(od
+        .write(0x1000, 0x00, ObjectValue::Unsigned32(0x192))
+        .is_ok());
+    assert!(od.read(0x2000, 0x00).is_ok());
+}
+
+#[test]
+fn test_object_dictionary_write_out_of_range() {
+    let mut od = ObjectDictionary::new();
+    od.add_with_attributes(
+        0x2000,
+        0x00,
+        ObjectValue::Integer16(0),
+        ObjectAttributes {
+            low_limit: Some(ObjectValue::Integer16(-100)),
+            high_limit: Some(ObjectValue::Integer16(100)),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        od.write(0x2000, 0x00, ObjectValue::Integer16(101)),
+        Err(ObjectDictionaryError::ValueTooHigh)
+    );
+    assert_eq!(
+        od.write(0x2000, 0x00, ObjectValue::Integer16(-101)),
+        Err(ObjectDictionaryError::ValueTooLow)
+    );
+    assert!(od.write(0x2000, 0x00, ObjectValue::Integer16(100)).is_ok());
+    assert!(od.write(0x2000, 0x00, ObjectValue::Integer16(-100)).is_ok());
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Integer16(-100)));
+}
+
+#[test]
+fn test_object_dictionary_restore_defaults() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1017, 0x00, ObjectValue::Unsigned16(1000));
+    od.add(0x2000, 0x00, ObjectValue::Integer32(410));
+    od.write(0x1017, 0x00, ObjectValue::Unsigned16(500))
+        .unwrap();
+    od.write(0x2000, 0x00, ObjectValue::Integer32(360)).unwrap();
+
+    let subscriber = Rc::new(RefCell::new(MySubscriber { value: 0 }));
+    od.subscribe(0x2000, 0x00, subscriber.clone()).unwrap();
+
+    od.restore_defaults(ParameterArea::Application);
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(500)));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Integer32(410)));
+    assert_eq!(subscriber.borrow().value, 410);
+
+    od.restore_defaults(ParameterArea::Communication);
+    assert_eq!(od.read(0x1017, 0x00), Ok(ObjectValue::Unsigned16(1000)));
+    assert_eq!(
+        od.default_value(0x1017, 0x00),
+        Ok(&ObjectValue::Unsigned16(1000))
+    );
+}
+
+fn round_trip(value: ObjectValue, bytes: Vec<u8>) {
+    assert_eq!(value.to_bytes(), bytes);
+    assert_eq!(
+        ObjectValue::from_bytes(value.data_type(), &bytes),
+        Ok(value)
+    );
+}
+
+#[test]
+fn test_object_value_integers_to_from_bytes() {
+    round_trip(ObjectValue::Boolean(true), vec![0x01]);
+    round_trip(ObjectValue::Integer8(-2), vec![0xFE]);
+    round_trip(ObjectValue::Integer16(-2), vec![0xFE, 0xFF]);
+    round_trip(ObjectValue::Integer24(-2), vec![0xFE, 0xFF, 0xFF]);
+    round_trip(ObjectValue::Integer24(0x123456), vec![0x56, 0x34, 0x12]);
+    round_trip(
+        ObjectValue::Integer32(0x12345678),
+        vec![0x78, 0x56, 0x34, 0x12],
+    );
+    round_trip(
+        ObjectValue::Integer40(-2),
+        vec![0xFE, 0xFF, 0xFF, 0xFF, 0xFF],
+    );
+    round_trip(ObjectValue::Integer48(0x1234), vec![0x34, 0x12, 0, 0, 0, 0]);
+    round_trip(ObjectValue::Integer56(-1), vec![0xFF; 7]);
+    round_trip(
+        ObjectValue::Integer64(i64::MIN),
+        vec![0, 0, 0, 0, 0, 0, 0, 0x80],
+    );
+    round_trip(ObjectValue::Unsigned8(0xAB), vec![0xAB]);
+    round_trip(ObjectValue::Unsigned16(0xABCD), vec![0xCD, 0xAB]);
+    round_trip(ObjectValue::Unsigned24(0xABCDEF), vec![0xEF, 0xCD, 0xAB]);
+    round_trip(ObjectValue::Unsigned32(0x1), vec![0x01, 0, 0, 0]);
+    round_trip(
+        ObjectValue::Unsigned40(0xFF_0000_0001),
+        vec![1, 0, 0, 0, 0xFF],
+    );
+    round_trip(ObjectValue::Unsigned48(0x1), vec![1, 0, 0, 0, 0, 0]);
+    round_trip(
+        ObjectValue::Unsigned56(0x80_0000_0000_0000),
+        vec![0, 0, 0, 0, 0, 0, 0x80],
+    );
+    round_trip(
+        ObjectValue::Unsigned64(0x0102_0304_0506_0708),
+        vec![8, 7, 6, 5, 4, 3, 2, 1],
+    );
+}
+
+#[test]
+fn test_object_value_reals_to_from_bytes() {
+    round_trip(ObjectValue::Real32(1.5), vec![0x00, 0x00, 0xC0, 0x3F]);
+    round_trip(ObjectValue::Real64(1.5), 1.5f64.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_object_value_strings_to_from_bytes() {
+    round_trip(
+        ObjectValue::VisibleString(String::from("abc")),
+        b"abc".to_vec(),
+    );
+    round_trip(ObjectValue::OctetString(String::from("")), Vec::new());
+    round_trip(
+        ObjectValue::UnicodeString(String::from("aé")),
+        vec![0x61, 0x00, 0xE9, 0x00],
+    );
+    round_trip(
+        ObjectValue::Domain(vec![1, 2, 3, 4, 5, 6]),
+        vec![1, 2, 3, 4, 5, 6],
+    );
+}
+
+#[test]
+fn test_object_value_time_to_from_bytes() {
+    round_trip(
+        ObjectValue::TimeOfDay(TimeOfDay {
+            milliseconds: 0x0123_4567,
+            days: 0x89AB,
+        }),
+        vec![0x67, 0x45, 0x23, 0x01, 0xAB, 0x89],
+    );
+    round_trip(
+        ObjectValue::TimeDifference(TimeDifference {
+            milliseconds: 1000,
+            days: 1,
+        }),
+        vec![0xE8, 0x03, 0x00, 0x00, 0x01, 0x00],
+    );
+}
+
+#[test]
+fn test_object_value_from_bytes_wrong_length() {
+    assert_eq!(
+        ObjectValue::from_bytes(DataType::Unsigned48, &[1, 2, 3]),
+        Err(ObjectDictionaryError::LengthTooLow)
+    );
+    assert_eq!(
+        ObjectValue::from_bytes(DataType::Integer24, &[1, 2, 3, 4]),
+        Err(ObjectDictionaryError::LengthTooHigh)
+    );
+    assert_eq!(
+        ObjectValue::from_bytes(DataType::UnicodeString, &[0x61]),
+        Err(ObjectDictionaryError::DataTypeMismatch)
+    );
+}
+
+#[test]
+fn test_data_type_codes() {
+    for code in 0x0000..=0x0020 {
+        if let Some(data_type) = DataType::from_code(code) {
+            assert_eq!(data_type.code(), code);
+        }
+    }
+    assert_eq!(DataType::from_code(0x0001), Some(DataType::Boolean));
+    assert_eq!(DataType::from_code(0x0007), Some(DataType::Unsigned32));
+    assert_eq!(DataType::from_code(0x000F), Some(DataType::Domain));
+    assert_eq!(DataType::from_code(0x001B), Some(DataType::Unsigned64));
+    assert_eq!(DataType::from_code(0x000E), None);
+    assert_eq!(DataType::from_code(0x0017), None);
+    assert_eq!(ObjectValue::Real64(0.0).data_type(), DataType::Real64);
+}
+
+#[test]
+fn test_object_type_from_added_entries() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1017, 0x00, ObjectValue::Unsigned16(0));
+    od.add(0x1F50, 0x00, ObjectValue::Domain(Vec::new()));
+    od.add(0x2000, 0x01, ObjectValue::Unsigned32(1));
+    od.add(0x2000, 0x03, ObjectValue::Unsigned32(3));
+
+    assert_eq!(od.object_type(0x1017), Ok(ObjectType::Var));
+    assert_eq!(od.object_type(0x1F50), Ok(ObjectType::Domain));
+    assert_eq!(od.object_type(0x2000), Ok(ObjectType::Record));
+    assert_eq!(
+        od.object_type(0x3000),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+    assert_eq!(ObjectType::from_code(0x8), Some(ObjectType::Array));
+    assert_eq!(ObjectType::Record.code(), 0x9);
+}
+
+#[test]
+fn test_highest_sub_index_is_maintained() {
+    let mut od = ObjectDictionary::new();
+    od.add_object(0x2000, ObjectType::Array, Some(String::from("Values")));
+    od.add(0x2000, 0x01, ObjectValue::Integer16(1));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(1)));
+    assert_eq!(
+        od.attributes(0x2000, 0x00).unwrap().access_type,
+        AccessType::Const
+    );
+
+    od.add(0x2000, 0x04, ObjectValue::Integer16(4));
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned8(4)));
+    assert_eq!(
+        od.default_value(0x2000, 0x00),
+        Ok(&ObjectValue::Unsigned8(4))
+    );
+    assert_eq!(od.object_type(0x2000), Ok(ObjectType::Array));
+    assert_eq!(od.object_name(0x2000), Ok(Some("Values")));
+}
+
+#[test]
+fn test_writable_sub_index_zero() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x1600, 0x00, ObjectValue::Unsigned8(0));
+    od.add(0x1600, 0x01, ObjectValue::Unsigned32(0x2000_0110));
+    od.add(0x1600, 0x02, ObjectValue::Unsigned32(0x2001_0008));
+
+    // Sub-index 0 counts the valid entries and is not adjusted.
+    assert_eq!(od.read(0x1600, 0x00), Ok(ObjectValue::Unsigned8(0)));
+    assert_eq!(od.write(0x1600, 0x00, ObjectValue::Unsigned8(2)), Ok(()));
+    assert_eq!(
+        od.remote_write(0x1600, 0x00, ObjectValue::Unsigned8(3)),
+        Err(ObjectDictionaryError::ValueTooHigh)
+    );
+    assert_eq!(od.read(0x1600, 0x00), Ok(ObjectValue::Unsigned8(2)));
+}
+
+#[test]
+fn test_enumerate_objects() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x02, ObjectValue::Unsigned8(2));
+    od.add(0x1017, 0x00, ObjectValue::Unsigned16(0));
+    od.add(0x2000, 0x01, ObjectValue::Unsigned8(1));
+
+    assert_eq!(od.indices(), vec![0x1017, 0x2000]);
+    assert_eq!(od.sub_indices(0x2000), Ok(vec![0x00, 0x01, 0x02]));
+    assert_eq!(
+        od.entries(),
+        vec![
+            (0x1017, 0x00),
+            (0x2000, 0x00),
+            (0x2000, 0x01),
+            (0x2000, 0x02)
+        ]
+    );
+    assert_eq!(
+        od.sub_indices(0x3000),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+}
+
+#[test]
+fn test_object_dictionary_provider() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x00, ObjectValue::Unsigned32(0));
+    od.add(0x2001, 0x00, ObjectValue::Unsigned16(0));
+    let counter = Rc::new(RefCell::new(Counter { count: 0 }));
+    assert_eq!(od.set_provider(0x2000, 0x00, counter.clone()), Ok(()));
+    assert_eq!(od.set_provider(0x2001, 0x00, counter.clone()), Ok(()));
+
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Unsigned32(1)));
+    assert_eq!(od.remote_read(0x2000, 0x00), Ok(ObjectValue::Unsigned32(2)));
+    assert_eq!(od.data_type(0x2000, 0x00), Ok(DataType::Unsigned32));
+    assert_eq!(counter.borrow().count, 2);
+    assert_eq!(
+        od.read(0x2001, 0x00),
+        Err(ObjectDictionaryError::DataTypeMismatch)
+    );
+    assert_eq!(
+        od.set_provider(0x3000, 0x00, counter),
+        Err(ObjectDictionaryError::ObjectDoesNotExist)
+    );
+}
+
+#[test]
+fn test_object_dictionary_validator() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x00, ObjectValue::Integer32(0));
+    let subscriber = Rc::new(RefCell::new(MySubscriber { value: 0 }));
+    od.subscribe(0x2000, 0x00, subscriber.clone()).unwrap();
+    od.add_validator(0x2000, 0x00, Rc::new(RefCell::new(EvenOnly)))
+        .unwrap();
+
+    assert_eq!(
+        od.remote_write(0x2000, 0x00, ObjectValue::Integer32(3)),
+        Err(ObjectDictionaryError::DeviceState)
+    );
+    assert_eq!(ObjectDictionaryError::DeviceState.abort_code(), 0x0800_0022);
+    assert_eq!(od.read(0x2000, 0x00), Ok(ObjectValue::Integer32(0)));
+    assert_eq!(subscriber.borrow().value, 0);
+
+    assert_eq!(
+        od.remote_write(0x2000, 0x00, ObjectValue::Integer32(4)),
+        Ok(())
+    );
+    assert_eq!(subscriber.borrow().value, 4);
+
+    // Local writes are not validated.
+    assert_eq!(od.write(0x2000, 0x00, ObjectValue::Integer32(5)), Ok(()));
+    assert_eq!(subscriber.borrow().value, 5);
+}
+
+#[test]
+fn test_object_dictionary_range_validator() {
+    let mut od = ObjectDictionary::new();
+    od.add(0x2000, 0x00, ObjectValue::Integer32(0));
+    od.add_range_validator(0x2000..=0x2FFF, Rc::new(RefCell::new(EvenOnly)));
+    od.add(0x2001, 0x00, ObjectValue::Integer32(0));
+    od.add(0x3000, 0x00, ObjectValue::Integer32(0));
+
+    assert_eq!(
+        od.remote_write(0x2000, 0x00, ObjectValue::Integer32(3)),
+        Err(ObjectDictionaryError::DeviceState)
+    );
+    assert_eq!(
+        od.remote_write(0x2001, 0x00, ObjectValue::Integer32(3)),
+        Err(ObjectDictionaryError::DeviceState)
+    );
+    assert_eq!(
+        od.remote_write(0x3000, 0x00, ObjectValue::Integer32(3)),
+        Ok(())
+    );
+}
@@ -0,0 +1,53 @@
+extern crate canopen_rs;
+
+use std::fs;
+
+use canopen_rs::od::ParameterArea;
+use canopen_rs::storage::{FileStorage, MemoryStorage, Storage};
+
+fn check_storage(storage: &mut dyn Storage) {
+    assert_eq!(storage.load(ParameterArea::Application).unwrap(), None);
+
+    storage
+        .save(ParameterArea::Application, &[0x01, 0x02])
+        .unwrap();
+    storage.save(ParameterArea::Communication, &[0x03]).unwrap();
+    assert_eq!(
+        storage.load(ParameterArea::Application).unwrap(),
+        Some(vec![0x01, 0x02])
+    );
+    assert_eq!(
+        storage.load(ParameterArea::Communication).unwrap(),
+        Some(vec![0x03])
+    );
+
+    storage.clear(ParameterArea::Application).unwrap();
+    storage.clear(ParameterArea::Application).unwrap();
+    assert_eq!(storage.load(ParameterArea::Application).unwrap(), None);
+    assert_eq!(
+        storage.load(ParameterArea::Communication).unwrap(),
+        Some(vec![0x03])
+    );
+}
+
+#[test]
+fn test_memory_storage() {
+    check_storage(&mut MemoryStorage::new());
+}
+
+#[test]
+fn test_file_storage() {
+    let directory = std::env::temp_dir().join(format!("canopen-rs-storage-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+
+    check_storage(&mut FileStorage::new(&directory));
+    // A new instance sees what the previous one stored.
+    assert_eq!(
+        FileStorage::new(&directory)
+            .load(ParameterArea::Communication)
+            .unwrap(),
+        Some(vec![0x03])
+    );
+
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,157 @@
+extern crate canopen_rs;
+
+use canopen_rs::od::{AccessType, ObjectValue};
+use canopen_rs::{eds, xdd};
+
+const XDD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<ISO15745ProfileContainer xmlns="http://www.canopen.org/xml/1.0">
+  <ISO15745Profile>
+    <ProfileBody xsi:type="ProfileBody_Device_CANopen">
+      <DeviceIdentity><vendorName>Vendor &amp; Co</vendorName></DeviceIdentity>
+    </ProfileBody>
+  </ISO15745Profile>
+  <ISO15745Profile>
+    <ProfileBody xsi:type="ProfileBody_CommunicationNetwork_CANopen">
+      <ApplicationLayers>
+        <CANopenObjectList>
+          <!-- <CANopenObject index="9999"/> -->
+          <CANopenObject index="1000" name="Device type" objectType="7"
+              dataType="0007" accessType="ro" defaultValue="0x00020192" PDOmapping="no"/>
+          <CANopenObject index="1018" name="Identity object" objectType="9" subNumber="2">
+            <CANopenSubObject subIndex="00" name="Highest sub-index supported"
+                objectType="7" dataType="0005" accessType="const" defaultValue="1"/>
+            <CANopenSubObject subIndex="01" name="Vendor-ID" objectType="7"
+                dataType="0007" accessType="ro" defaultValue="0x12345678"/>
+          </CANopenObject>
+          <CANopenObject index="1200" name="SDO server parameter" objectType="9" subNumber="1">
+            <CANopenSubObject subIndex="01" name="COB-ID client to server" objectType="7"
+                dataType="0007" accessType="ro" defaultValue="$NODEID+0x600"/>
+          </CANopenObject>
+          <CANopenObject index="2000" name="Setpoint" objectType="7" dataType="0003"
+              accessType="rww" defaultValue="-100" lowLimit="-1000" highLimit="1000"
+              PDOmapping="RPDO"/>
+          <CANopenObject index="2001" name="Device name &quot;A&quot;" objectType="7"
+              dataType="0009" accessType="ro" defaultValue='My device'/>
+        </CANopenObjectList>
+      </ApplicationLayers>
+    </ProfileBody>
+  </ISO15745Profile>
+</ISO15745ProfileContainer>
+"#;
+
+const EDS: &str = "\
+[1000]
+ParameterName=Device type
+ObjectType=0x7
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x00020192
+PDOMapping=0
+
+[1018sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=const
+DefaultValue=1
+
+[1018sub1]
+ParameterName=Vendor-ID
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x12345678
+
+[1200sub1]
+ParameterName=COB-ID client to server
+DataType=0x0007
+AccessType=ro
+DefaultValue=$NODEID+0x600
+
+[2000]
+ParameterName=Setpoint
+DataType=0x0003
+AccessType=rww
+DefaultValue=-100
+LowLimit=-1000
+HighLimit=1000
+PDOMapping=1
+
+[2001]
+ParameterName=Device name \"A\"
+DataType=0x0009
+AccessType=ro
+DefaultValue=My device
+";
+
+fn parse_error(input: &str) -> xdd::XddError {
+    match xdd::parse(input, 1) {
+        Err(error) => error,
+        Ok(_) => panic!("expected a parse error"),
+    }
+}
+
+#[test]
+fn test_parse_matches_eds() {
+    let from_xdd = xdd::parse(XDD, 3).unwrap();
+    let from_eds = eds::parse(EDS, 3).unwrap();
+
+    assert_eq!(from_xdd.entries(), from_eds.entries());
+    for (index, sub_index) in from_eds.entries() {
+        assert_eq!(
+            from_xdd.read(index, sub_index),
+            from_eds.read(index, sub_index)
+        );
+        assert_eq!(
+            from_xdd.attributes(index, sub_index),
+            from_eds.attributes(index, sub_index)
+        );
+    }
+}
+
+#[test]
+fn test_parse_values_and_attributes() {
+    let od = xdd::parse(XDD, 3).unwrap();
+    assert_eq!(od.read(0x1200, 1), Ok(ObjectValue::Unsigned32(0x603)));
+    let attributes = od.attributes(0x2000, 0).unwrap();
+    assert_eq!(attributes.access_type, AccessType::ReadWriteWrite);
+    assert!(attributes.pdo_mappable);
+    assert!(!od.attributes(0x1000, 0).unwrap().pdo_mappable);
+}
+
+#[test]
+fn test_parse_xdc() {
+    let xdc = r#"<ISO15745ProfileContainer>
+  <CANopenObjectList>
+    <CANopenObject index="1017" name="Producer heartbeat time" objectType="7"
+        dataType="0006" accessType="rw" defaultValue="0" actualValue="500"/>
+  </CANopenObjectList>
+  <NetworkManagement>
+    <deviceCommissioning nodeID="12" nodeName="drive" actualBaudRate="250 Kbps"/>
+  </NetworkManagement>
+</ISO15745ProfileContainer>"#;
+
+    let (node_id, od) = xdd::parse_xdc(xdc).unwrap();
+    assert_eq!(node_id, 12);
+    assert_eq!(od.read(0x1017, 0), Ok(ObjectValue::Unsigned16(500)));
+    assert_eq!(od.default_value(0x1017, 0), Ok(&ObjectValue::Unsigned16(0)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_error("<a>\n<b>\n</a>").line, 3);
+    assert_eq!(parse_error("<a>\n<b/>\n").line, 1);
+    assert_eq!(parse_error("<a>\n<!-- comment\n").line, 2);
+    assert_eq!(parse_error("\n<a b=\"1>\n").line, 2);
+
+    let error = parse_error(
+        "<CANopenObjectList>\n<CANopenObject index=\"1000\" objectType=\"7\"\n\
+         dataType=\"0005\" accessType=\"ro\" defaultValue=\"300\"/>\n</CANopenObjectList>",
+    );
+    assert_eq!(error.line, 2);
+    assert_eq!(
+        error.to_string(),
+        "line 2: value '300' out of range for Unsigned8"
+    );
+
+    assert_eq!(parse_error("<CANopenObject index=\"x\"/>").line, 1);
+    assert_eq!(parse_error("\n<CANopenSubObject subIndex=\"1\"/>").line, 2);
+}